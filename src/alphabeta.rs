@@ -0,0 +1,395 @@
+//! Negamax alpha-beta search with iterative deepening, as an alternative to MCTS.
+//!
+//! Moves are searched captures first, then other pushes, then the rest, with the best move
+//! of the previous iteration always tried first. At the horizon a quiescence search keeps
+//! following captures and pushes so positions are not scored in the middle of an exchange.
+//! Scores come from `eval::Weights` and are always for the player to move. Bounds and best
+//! moves are kept in a transposition table, which can be carried from one search to the next.
+//!
+//! The same `SearchLimits` as for MCTS apply, with every completed depth counting as one
+//! iteration and every position visited, quiescence included, as a node.
+
+use std::time::Instant;
+
+use crate::eval::{Weights, WIN_SCORE};
+use crate::game::{Game, Move, MoveList, Space, MAX_MOVES};
+use crate::search::{SearchLimits, SearchProgress};
+use crate::transposition::TranspositionTable;
+
+// deeper than any iterative deepening search gets in practice
+const MAX_PLY: usize = 64;
+// how many pushes in a row quiescence follows
+const QUIESCENCE_DEPTH: usize = 6;
+// how often the limits are checked, in nodes
+const CHECK_INTERVAL: usize = 1024;
+// scores this close to a win are forced wins found by the search
+const WIN_THRESHOLD: i32 = WIN_SCORE - MAX_PLY as i32 * 2;
+// the size of the table a search makes for itself
+pub const DEFAULT_TABLE_ENTRIES: usize = 1 << 17;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    // the score is at least this
+    Lower,
+    // the score is at most this
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TableEntry {
+    pub depth: u8,
+    pub score: i32,
+    pub bound: Bound,
+    pub best_move: Option<Move>,
+}
+
+// Wins are scored by their distance from the root, but stored by their distance from the
+// position itself so the entry holds wherever the position is reached.
+pub fn score_to_table(score: i32, ply: usize) -> i32 {
+    if score >= WIN_THRESHOLD {
+        score + ply as i32
+    } else if score <= -WIN_THRESHOLD {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+pub fn score_from_table(score: i32, ply: usize) -> i32 {
+    if score >= WIN_THRESHOLD {
+        score - ply as i32
+    } else if score <= -WIN_THRESHOLD {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AlphaBetaResult {
+    pub best_move: Option<Move>,
+    pub principal_variation: Vec<Move>,
+    // for the player to move at the root
+    pub score: i32,
+    pub depth: usize,
+    pub progress: SearchProgress,
+}
+
+impl AlphaBetaResult {
+    // the score as a rough chance of winning, a marble up under the default weights is
+    // worth about 90%
+    pub fn win_rate(&self) -> f32 {
+        if self.score.abs() >= WIN_THRESHOLD {
+            return if self.score > 0 { 1.0 } else { 0.0 };
+        }
+        1.0 / (1.0 + (-self.score as f32 / 450.0).exp())
+    }
+}
+
+// 2 for a capture, 1 for any other move that moves an opposing marble, 0 otherwise
+fn move_class(game: &Game, next_state: &Game) -> u8 {
+    if next_state.white_pieces < game.white_pieces || next_state.black_pieces < game.black_pieces {
+        return 2;
+    }
+
+    let theirs = Space::Occupied(game.player.opponent());
+    let pushed = (0..9).any(|x| (0..9).any(|y| game.space(x, y) == theirs && next_state.space(x, y) != theirs));
+    u8::from(pushed)
+}
+
+struct Searcher<'a> {
+    weights: &'a Weights,
+    limits: &'a SearchLimits,
+    table: &'a mut TranspositionTable<TableEntry>,
+    start: Instant,
+    nodes: usize,
+    completed_depths: usize,
+    aborted: bool,
+    // only set once the first depth is complete, so there is always a move to play
+    may_abort: bool,
+    // pv[ply] is the best line found from ply on in the current search
+    pv: Vec<Vec<Move>>,
+    // the principal variation of the previous depth, searched first
+    pv_hint: Vec<Move>,
+    on_pv: bool,
+    // moves[ply] holds the moves of the position at ply in the order to search them
+    moves: Vec<MoveList>,
+    // where the moves are generated before they are ordered
+    generated: MoveList,
+}
+
+impl Searcher<'_> {
+    fn progress(&self) -> SearchProgress {
+        SearchProgress { iterations: self.completed_depths, nodes: self.nodes, elapsed: self.start.elapsed() }
+    }
+
+    fn visit(&mut self) -> bool {
+        self.nodes += 1;
+
+        if self.may_abort && self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            let progress = self.progress();
+            // the iteration limit is checked between depths
+            let limits = SearchLimits { iterations: None, ..self.limits.clone() };
+            if limits.reached(&progress) {
+                self.aborted = true;
+            }
+        }
+
+        !self.aborted
+    }
+
+    // Fills `moves[ply]` with the legal moves in the order to search them, `first` first, and
+    // returns how many of them come before the quiet moves.
+    fn order_moves(&mut self, game: &Game, first: Option<Move>, ply: usize) -> usize {
+        game.generate_moves(&mut self.generated);
+
+        let mut classes = [0; MAX_MOVES];
+        for (class, next_move) in classes.iter_mut().zip(self.generated.iter()) {
+            *class = if Some(*next_move) == first { 3 } else { move_class(game, &game.make_move(next_move)) };
+        }
+
+        let ordered = &mut self.moves[ply];
+        ordered.clear();
+        for class in (0..=3).rev() {
+            for (index, next_move) in self.generated.iter().enumerate() {
+                if classes[index] == class {
+                    ordered.push(*next_move);
+                }
+            }
+        }
+
+        classes[..self.generated.len()].iter().filter(|&&class| class > 0).count()
+    }
+
+    // Table cutoffs end the principal variation where they happen, so it is continued with
+    // the best moves stored for the positions along it, up to `depth` moves in all.
+    fn extend_from_table(&self, root: &Game, depth: usize) -> Vec<Move> {
+        let mut line = self.pv[0].clone();
+        let mut game = *root;
+        for next_move in &line {
+            game = game.make_move(next_move);
+        }
+
+        while line.len() < depth && !game.game_over {
+            let Some(next_move) = self.table.get(&game).and_then(|entry| entry.best_move) else {
+                break;
+            };
+            if !game.get_legal_moves().contains(&next_move) {
+                break;
+            }
+            game = game.make_move(&next_move);
+            line.push(next_move);
+        }
+
+        line
+    }
+
+    fn quiescence(&mut self, game: &Game, mut alpha: i32, beta: i32, ply: usize, remaining: usize) -> i32 {
+        if !self.visit() {
+            return 0;
+        }
+        if game.game_over {
+            return self.terminal_score(game, ply);
+        }
+
+        let stand_pat = self.weights.evaluate(game);
+        if stand_pat >= beta || remaining == 0 {
+            return stand_pat;
+        }
+        alpha = alpha.max(stand_pat);
+
+        let loud = self.order_moves(game, None, ply);
+        for index in 0..loud {
+            let next_state = game.make_move(&self.moves[ply][index]);
+            let score = -self.quiescence(&next_state, -beta, -alpha, ply + 1, remaining - 1);
+            if self.aborted {
+                return 0;
+            }
+            if score >= beta {
+                return score;
+            }
+            alpha = alpha.max(score);
+        }
+
+        alpha
+    }
+
+    // wins found sooner score higher, so the search heads for the quickest one
+    fn terminal_score(&self, game: &Game, ply: usize) -> i32 {
+        match game.winner {
+            Some(winner) if winner == game.player => WIN_SCORE - ply as i32,
+            Some(_) => -(WIN_SCORE - ply as i32),
+            None => 0,
+        }
+    }
+
+    fn negamax(&mut self, game: &Game, depth: usize, mut alpha: i32, beta: i32, ply: usize) -> i32 {
+        self.pv[ply].clear();
+
+        if game.game_over {
+            self.visit();
+            return self.terminal_score(game, ply);
+        }
+        if depth == 0 || ply + 1 >= MAX_PLY {
+            return self.quiescence(game, alpha, beta, ply, QUIESCENCE_DEPTH);
+        }
+        if !self.visit() {
+            return 0;
+        }
+
+        let original_alpha = alpha;
+        let mut table_move = None;
+        if let Some(entry) = self.table.get(game).copied() {
+            table_move = entry.best_move;
+
+            // the root is always searched so it has a principal variation
+            if ply > 0 && entry.depth as usize >= depth {
+                let score = score_from_table(entry.score, ply);
+                match entry.bound {
+                    Bound::Exact => return score,
+                    Bound::Lower if score >= beta => return score,
+                    Bound::Upper if score <= alpha => return score,
+                    _ => (),
+                }
+            }
+        }
+
+        // follow the principal variation of the previous iteration while still on it
+        let pv_move = self.pv_hint.get(ply).copied().filter(|_| self.on_pv);
+        let mut best = -WIN_SCORE - 1;
+        let mut best_move = None;
+
+        self.order_moves(game, pv_move.or(table_move), ply);
+        for index in 0..self.moves[ply].len() {
+            let next_move = self.moves[ply][index];
+            let next_state = game.make_move(&next_move);
+            self.on_pv = self.on_pv && index == 0 && pv_move == Some(next_move);
+
+            let score = -self.negamax(&next_state, depth - 1, -beta, -alpha, ply + 1);
+            if self.aborted {
+                return 0;
+            }
+
+            if score > best {
+                best = score;
+                best_move = Some(next_move);
+                if score > alpha {
+                    alpha = score;
+                    let (head, tail) = self.pv.split_at_mut(ply + 1);
+                    head[ply].clear();
+                    head[ply].push(next_move);
+                    head[ply].extend_from_slice(&tail[0]);
+                }
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        let bound = if best <= original_alpha {
+            Bound::Upper
+        } else if best >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        let entry = TableEntry { depth: depth as u8, score: score_to_table(best, ply), bound, best_move };
+        self.table.insert(game, entry, depth as u32);
+
+        best
+    }
+}
+
+// Searches `root` one depth deeper at a time until one of the limits is reached, calling
+// `on_depth` after every completed depth. The first depth always completes, so unless the
+// game is over there is always a best move.
+pub fn alphabeta_search_with_table<F>(
+    root: Game,
+    limits: &SearchLimits,
+    weights: &Weights,
+    table: &mut TranspositionTable<TableEntry>,
+    mut on_depth: F,
+) -> AlphaBetaResult
+where
+    F: FnMut(&AlphaBetaResult),
+{
+    table.new_search();
+
+    let mut searcher = Searcher {
+        weights,
+        limits,
+        table,
+        start: Instant::now(),
+        nodes: 0,
+        completed_depths: 0,
+        aborted: false,
+        may_abort: false,
+        pv: vec![Vec::new(); MAX_PLY + 1],
+        pv_hint: Vec::new(),
+        on_pv: false,
+        moves: vec![MoveList::new(); MAX_PLY + QUIESCENCE_DEPTH + 1],
+        generated: MoveList::new(),
+    };
+    let mut result = AlphaBetaResult {
+        best_move: None,
+        principal_variation: Vec::new(),
+        score: 0,
+        depth: 0,
+        progress: searcher.progress(),
+    };
+
+    if root.game_over {
+        return result;
+    }
+
+    for depth in 1..MAX_PLY {
+        searcher.on_pv = true;
+        let score = searcher.negamax(&root, depth, -WIN_SCORE - 1, WIN_SCORE + 1, 0);
+        if searcher.aborted {
+            break;
+        }
+
+        searcher.completed_depths = depth;
+        searcher.may_abort = true;
+        searcher.pv_hint = searcher.extend_from_table(&root, depth);
+
+        result = AlphaBetaResult {
+            best_move: searcher.pv[0].first().copied(),
+            principal_variation: searcher.pv_hint.clone(),
+            score,
+            depth,
+            progress: searcher.progress(),
+        };
+        on_depth(&result);
+
+        let only_move = root.get_legal_moves().len() == 1;
+        if limits.reached(&result.progress) || score.abs() >= WIN_THRESHOLD || (limits.stop_when_decided && only_move) {
+            break;
+        }
+    }
+
+    result.progress = searcher.progress();
+    result
+}
+
+pub fn alphabeta_search_with_progress<F>(
+    root: Game,
+    limits: &SearchLimits,
+    weights: &Weights,
+    on_depth: F,
+) -> AlphaBetaResult
+where
+    F: FnMut(&AlphaBetaResult),
+{
+    let mut table = TranspositionTable::new(DEFAULT_TABLE_ENTRIES);
+    alphabeta_search_with_table(root, limits, weights, &mut table, on_depth)
+}
+
+pub fn alphabeta_search_with_limits(root: Game, limits: &SearchLimits, weights: &Weights) -> AlphaBetaResult {
+    alphabeta_search_with_progress(root, limits, weights, |_| ())
+}
+
+pub fn alphabeta_search(root: Game, depth: usize) -> AlphaBetaResult {
+    alphabeta_search_with_limits(root, &SearchLimits::iterations(depth.max(1)), &Weights::default())
+}
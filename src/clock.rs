@@ -0,0 +1,243 @@
+//! Game clocks and the policy deciding how much thinking time the engine takes per move.
+//!
+//! Time controls are written compactly, with all times in seconds:
+//!
+//! ```text
+//! 300+5      5 minutes each plus a 5 second increment after every move (Fischer)
+//! 600+30x3   10 minutes each, then three 30 second byoyomi periods
+//! 10/move    a fixed 10 seconds for every move
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::game::{Game, Player};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TimeControl {
+    Fischer { base: Duration, increment: Duration },
+    Byoyomi { base: Duration, period: Duration, periods: u32 },
+    PerMove(Duration),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseTimeControlError(String);
+
+impl fmt::Display for ParseTimeControlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?} is not a time control, expected e.g. 300+5, 600+30x3 or 10/move", self.0)
+    }
+}
+
+impl std::error::Error for ParseTimeControlError {}
+
+fn format_secs(duration: Duration) -> String {
+    let secs = duration.as_secs_f64();
+    if secs.fract() == 0.0 {
+        format!("{}", secs as u64)
+    } else {
+        format!("{}", secs)
+    }
+}
+
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TimeControl::Fischer { base, increment } => {
+                write!(f, "{}+{}", format_secs(*base), format_secs(*increment))
+            }
+            TimeControl::Byoyomi { base, period, periods } => {
+                write!(f, "{}+{}x{}", format_secs(*base), format_secs(*period), periods)
+            }
+            TimeControl::PerMove(time) => write!(f, "{}/move", format_secs(*time)),
+        }
+    }
+}
+
+impl FromStr for TimeControl {
+    type Err = ParseTimeControlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseTimeControlError(s.to_string());
+        let secs = |text: &str| {
+            text.parse::<f64>()
+                .ok()
+                .filter(|secs| secs.is_finite() && *secs >= 0.0)
+                .map(Duration::from_secs_f64)
+                .ok_or_else(invalid)
+        };
+
+        let s = s.trim();
+        if let Some(time) = s.strip_suffix("/move") {
+            return Ok(TimeControl::PerMove(secs(time)?));
+        }
+
+        let (base, extra) = s.split_once('+').ok_or_else(invalid)?;
+        match extra.split_once('x') {
+            Some((period, periods)) => Ok(TimeControl::Byoyomi {
+                base: secs(base)?,
+                period: secs(period)?,
+                periods: periods.parse().map_err(|_| invalid())?,
+            }),
+            None => Ok(TimeControl::Fischer { base: secs(base)?, increment: secs(extra)? }),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PlayerClock {
+    pub remaining: Duration,
+    pub periods: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Clock {
+    pub control: TimeControl,
+    pub white: PlayerClock,
+    pub black: PlayerClock,
+    pub flagged: Option<Player>,
+}
+
+impl Clock {
+    pub fn new(control: TimeControl) -> Self {
+        let start = match control {
+            TimeControl::Fischer { base, .. } => PlayerClock { remaining: base, periods: 0 },
+            TimeControl::Byoyomi { base, periods, .. } => PlayerClock { remaining: base, periods },
+            TimeControl::PerMove(time) => PlayerClock { remaining: time, periods: 0 },
+        };
+
+        Clock { control, white: start, black: start, flagged: None }
+    }
+
+    pub fn player(&self, player: Player) -> &PlayerClock {
+        match player {
+            Player::White => &self.white,
+            Player::Black => &self.black,
+        }
+    }
+
+    fn player_mut(&mut self, player: Player) -> &mut PlayerClock {
+        match player {
+            Player::White => &mut self.white,
+            Player::Black => &mut self.black,
+        }
+    }
+
+    // the most time `player` can spend on the current move without losing on time
+    pub fn available(&self, player: Player) -> Duration {
+        let clock = self.player(player);
+
+        match self.control {
+            TimeControl::Byoyomi { period, .. } if clock.periods > 0 => clock.remaining + period,
+            _ => clock.remaining,
+        }
+    }
+
+    // Charges `used` to `player` for the move just made. Returns false, and records the
+    // player as flagged, if that took longer than they had.
+    pub fn punch(&mut self, player: Player, used: Duration) -> bool {
+        let control = self.control;
+        let clock = self.player_mut(player);
+
+        let in_time = match control {
+            TimeControl::Fischer { increment, .. } => match clock.remaining.checked_sub(used) {
+                Some(left) => {
+                    clock.remaining = left + increment;
+                    true
+                }
+                None => false,
+            },
+            TimeControl::Byoyomi { period, .. } => {
+                if let Some(left) = clock.remaining.checked_sub(used) {
+                    clock.remaining = left;
+                    true
+                } else {
+                    // main time is gone, each period that is used up entirely is lost
+                    let mut over = used - clock.remaining;
+                    clock.remaining = Duration::ZERO;
+                    while over >= period && clock.periods > 0 {
+                        over -= period;
+                        clock.periods -= 1;
+                    }
+                    clock.periods > 0
+                }
+            }
+            TimeControl::PerMove(time) => used <= time,
+        };
+
+        if !in_time {
+            self.flagged = Some(player);
+        }
+        in_time
+    }
+}
+
+impl fmt::Display for Clock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let show = |clock: &PlayerClock| match self.control {
+            TimeControl::Byoyomi { .. } if clock.remaining.is_zero() => {
+                format!("{} periods", clock.periods)
+            }
+            _ => format!("{:.1}s", clock.remaining.as_secs_f64()),
+        };
+
+        write!(f, "white {}, black {}", show(&self.white), show(&self.black))
+    }
+}
+
+// Decides how long the engine thinks on each move: an even share of the time left over the
+// moves the game is still expected to last, plus most of any time that comes back after
+// the move, never cutting closer to the flag than `safety_margin`.
+#[derive(Debug, Copy, Clone)]
+pub struct TimeManager {
+    pub expected_game_length: i16,
+    pub min_moves_to_go: i16,
+    pub safety_margin: Duration,
+}
+
+impl Default for TimeManager {
+    fn default() -> Self {
+        TimeManager {
+            expected_game_length: 160,
+            min_moves_to_go: 20,
+            safety_margin: Duration::from_millis(50),
+        }
+    }
+}
+
+impl TimeManager {
+    // the number of moves the player to move is still expected to make
+    pub fn moves_to_go(&self, game: &Game) -> u32 {
+        let left = (self.expected_game_length - game.move_number) / 2;
+        left.max(self.min_moves_to_go) as u32
+    }
+
+    pub fn allocate(&self, clock: &Clock, game: &Game) -> Duration {
+        let player = clock.player(game.player);
+        let moves_to_go = self.moves_to_go(game);
+
+        let budget = match clock.control {
+            TimeControl::Fischer { increment, .. } => player.remaining / moves_to_go + increment * 3 / 4,
+            TimeControl::Byoyomi { period, .. } if player.periods > 0 => player.remaining / moves_to_go + period,
+            TimeControl::Byoyomi { .. } => player.remaining / moves_to_go,
+            TimeControl::PerMove(time) => time,
+        };
+
+        budget.min(clock.available(game.player).saturating_sub(self.safety_margin))
+    }
+
+    // the same policy for controllers that only report raw clock readings
+    pub fn allocate_from(
+        &self,
+        game: &Game,
+        remaining: Duration,
+        increment: Duration,
+        moves_to_go: Option<u32>,
+    ) -> Duration {
+        let moves_to_go = moves_to_go.unwrap_or_else(|| self.moves_to_go(game)).max(1);
+        let budget = remaining / moves_to_go + increment * 3 / 4;
+
+        budget.min(remaining.saturating_sub(self.safety_margin))
+    }
+}
@@ -0,0 +1,250 @@
+//! Static evaluation of positions, for cutting playouts short, alpha-beta search and move
+//! ordering.
+//!
+//! A score is a weighted sum of features, each measured as the difference between the two
+//! sides. Weights can be loaded from a file in the `keyvalue` format, one weight per line.
+//! Weights that are not mentioned keep their default:
+//!
+//! ```text
+//! # material counts most
+//! material = 1000
+//! center = 20
+//! cohesion = 10
+//! edge_danger = 40
+//! pushing = 60
+//! ```
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::game::{in_bounds, Game, Player, Space, DIRS};
+use crate::keyvalue::{self, InvalidLine};
+
+// the scores of won and lost positions, beyond anything the features can add up to
+pub const WIN_SCORE: i32 = 1_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Weights {
+    // per marble more than the opponent
+    pub material: i32,
+    // per step the opponent's marbles are further from the centre in total
+    pub center: i32,
+    // per pair of touching marbles more than the opponent
+    pub cohesion: i32,
+    // per marble the opponent has more on the outer ring
+    pub edge_danger: i32,
+    // per push available more than the opponent has
+    pub pushing: i32,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights { material: 1000, center: 20, cohesion: 10, edge_danger: 40, pushing: 60 }
+    }
+}
+
+#[derive(Debug)]
+pub enum WeightsError {
+    Io(io::Error),
+    InvalidLine(InvalidLine),
+    UnknownWeight { line: usize, name: String },
+    InvalidValue { line: usize, value: String },
+}
+
+impl fmt::Display for WeightsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WeightsError::Io(error) => write!(f, "{}", error),
+            WeightsError::InvalidLine(error) => write!(f, "{}", error),
+            WeightsError::UnknownWeight { line, name } => write!(f, "line {}: unknown weight {:?}", line, name),
+            WeightsError::InvalidValue { line, value } => {
+                write!(f, "line {}: {:?} is not a whole number", line, value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for WeightsError {}
+
+impl From<io::Error> for WeightsError {
+    fn from(error: io::Error) -> Self {
+        WeightsError::Io(error)
+    }
+}
+
+impl From<InvalidLine> for WeightsError {
+    fn from(error: InvalidLine) -> Self {
+        WeightsError::InvalidLine(error)
+    }
+}
+
+impl FromStr for Weights {
+    type Err = WeightsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut weights = Weights::default();
+
+        for setting in keyvalue::parse(s) {
+            let (line, name, value) = setting?;
+            let weight = match name {
+                "material" => &mut weights.material,
+                "center" => &mut weights.center,
+                "cohesion" => &mut weights.cohesion,
+                "edge_danger" => &mut weights.edge_danger,
+                "pushing" => &mut weights.pushing,
+                _ => return Err(WeightsError::UnknownWeight { line, name: name.to_string() }),
+            };
+            *weight = value.parse().map_err(|_| WeightsError::InvalidValue { line, value: value.to_string() })?;
+        }
+
+        Ok(weights)
+    }
+}
+
+impl fmt::Display for Weights {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "material = {}", self.material)?;
+        writeln!(f, "center = {}", self.center)?;
+        writeln!(f, "cohesion = {}", self.cohesion)?;
+        writeln!(f, "edge_danger = {}", self.edge_danger)?;
+        writeln!(f, "pushing = {}", self.pushing)
+    }
+}
+
+fn ring(x: i32, y: i32) -> i32 {
+    let (dx, dy) = (x - 4, y - 4);
+    dx.abs().max(dy.abs()).max((dx - dy).abs())
+}
+
+// the sum over `player`'s marbles of their distance in steps from the centre cell
+pub fn center_distance(game: &Game, player: Player) -> i32 {
+    let mut total = 0;
+
+    for x in 0..9 {
+        for y in 0..9 {
+            if game.space(x, y) == Space::Occupied(player) {
+                total += ring(x as i32, y as i32);
+            }
+        }
+    }
+
+    total
+}
+
+// the number of pairs of `player`'s marbles that touch
+pub fn cohesion(game: &Game, player: Player) -> i32 {
+    let mut pairs = 0;
+
+    for x in 0..9 {
+        for y in 0..9 {
+            if game.space(x, y) != Space::Occupied(player) {
+                continue;
+            }
+            // the first three directions cover every pair exactly once
+            for dir in &DIRS[..3] {
+                let (nx, ny) = (x as i8 + dir.0, y as i8 + dir.1);
+                if in_bounds(nx, ny) && game.space(nx as usize, ny as usize) == Space::Occupied(player) {
+                    pairs += 1;
+                }
+            }
+        }
+    }
+
+    pairs
+}
+
+// the number of `player`'s marbles on the outer ring, one push from being lost
+pub fn edge_marbles(game: &Game, player: Player) -> i32 {
+    let mut count = 0;
+
+    for x in 0..9 {
+        for y in 0..9 {
+            if game.space(x, y) == Space::Occupied(player) && ring(x as i32, y as i32) == 4 {
+                count += 1;
+            }
+        }
+    }
+
+    count
+}
+
+// The number of sumitos `player` could play: a line of two or three of their marbles followed
+// by a shorter line of the opponent's, with an empty cell or the edge of the board behind it.
+// A longer line of their own can push with the marbles at its front.
+pub fn pushes(game: &Game, player: Player) -> i32 {
+    let cell = |x: i8, y: i8| {
+        if in_bounds(x, y) {
+            game.space(x as usize, y as usize)
+        } else {
+            Space::OutOfBounds
+        }
+    };
+    let mut count = 0;
+
+    for x in 0..9i8 {
+        for y in 0..9i8 {
+            if cell(x, y) != Space::Occupied(player) {
+                continue;
+            }
+
+            for dir in DIRS {
+                // each marble starts a group of up to three, which must reach the opponent
+                let mut pushers = 1;
+                while pushers < 3 && cell(x + dir.0 * pushers, y + dir.1 * pushers) == Space::Occupied(player) {
+                    pushers += 1;
+                }
+
+                let mut pushed = 0;
+                let (mut px, mut py) = (x + dir.0 * pushers, y + dir.1 * pushers);
+                while cell(px, py) == Space::Occupied(player.opponent()) {
+                    pushed += 1;
+                    px += dir.0;
+                    py += dir.1;
+                }
+
+                if pushed > 0 && pushed < pushers && matches!(cell(px, py), Space::Empty | Space::OutOfBounds) {
+                    count += 1;
+                }
+            }
+        }
+    }
+
+    count
+}
+
+impl Weights {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, WeightsError> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    // how good the position is for `player`, positive when they are better off
+    pub fn evaluate_for(&self, game: &Game, player: Player) -> i32 {
+        if game.game_over {
+            return match game.winner {
+                Some(winner) if winner == player => WIN_SCORE,
+                Some(_) => -WIN_SCORE,
+                None => 0,
+            };
+        }
+
+        let other = player.opponent();
+        let (own, theirs) = match player {
+            Player::White => (game.white_pieces, game.black_pieces),
+            Player::Black => (game.black_pieces, game.white_pieces),
+        };
+
+        self.material * (own - theirs) as i32
+            + self.center * (center_distance(game, other) - center_distance(game, player))
+            + self.cohesion * (cohesion(game, player) - cohesion(game, other))
+            + self.edge_danger * (edge_marbles(game, other) - edge_marbles(game, player))
+            + self.pushing * (pushes(game, player) - pushes(game, other))
+    }
+
+    // how good the position is for the player to move
+    pub fn evaluate(&self, game: &Game) -> i32 {
+        self.evaluate_for(game, game.player)
+    }
+}
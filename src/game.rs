@@ -1,11 +1,9 @@
 use std::fmt;
-use rand::seq::SliceRandom;
+use std::ops::Deref;
 
-fn variant_eq<T>(a: &T, b: &T) -> bool {
-    std::mem::discriminant(a) == std::mem::discriminant(b)
-}
+use crate::layout::Layout;
 
-static DIRS: [(i8, i8); 6] = [
+pub(crate) static DIRS: [(i8, i8); 6] = [
     (1, 0),
     (1, 1),
     (0, 1),
@@ -14,415 +12,396 @@ static DIRS: [(i8, i8); 6] = [
     (-1, 0),
 ];
 
-fn in_bounds(x: i8, y: i8) -> bool {
-    x >= 0 && x <= 8 && y >= 0 && y <= 8
+pub(crate) fn in_bounds(x: i8, y: i8) -> bool {
+    (0..=8).contains(&x) && (0..=8).contains(&y)
 }
 
-#[derive(Clone, Debug, Copy)]
+// The board is held as one bitboard per player over an 11 by 11 grid: the 9 by 9 array
+// cells are addressed by, with a border of one cell all round so a step off the edge lands
+// in the border rather than wrapping onto the next row. Cell (x, y) is bit 11 * (x + 1) + y + 1.
+const WIDTH: usize = 11;
+
+const fn bit(x: usize, y: usize) -> u32 {
+    (WIDTH * (x + 1) + y + 1) as u32
+}
+
+fn cell(bit: u32) -> (usize, usize) {
+    (bit as usize / WIDTH - 1, bit as usize % WIDTH - 1)
+}
+
+// the 61 cells of the board
+const CELLS: u128 = {
+    let mut cells = 0;
+    let mut x: usize = 0;
+    while x < 9 {
+        let mut y = 0;
+        while y < 9 {
+            if x.abs_diff(y) <= 4 {
+                cells |= 1 << bit(x, y);
+            }
+            y += 1;
+        }
+        x += 1;
+    }
+    cells
+};
+
+// how far along the grid one step in each of `DIRS` is
+const STEPS: [i32; 6] = {
+    let mut steps = [0; 6];
+    let mut i = 0;
+    while i < 6 {
+        steps[i] = DIRS[i].0 as i32 * WIDTH as i32 + DIRS[i].1 as i32;
+        i += 1;
+    }
+    steps
+};
+
+// every marble in `mask` moved one step in direction `dir`
+fn step(mask: u128, dir: usize) -> u128 {
+    let shift = STEPS[dir];
+    if shift > 0 {
+        mask << shift
+    } else {
+        mask >> -shift
+    }
+}
+
+// the cells whose neighbour in direction `dir` is in `mask`
+fn behind(mask: u128, dir: usize) -> u128 {
+    step(mask, 5 - dir)
+}
+
+// the cells of `mask`, lowest bit first
+fn cells(mut mask: u128) -> impl Iterator<Item = (usize, usize)> {
+    std::iter::from_fn(move || {
+        if mask == 0 {
+            return None;
+        }
+        let bit = mask.trailing_zeros();
+        mask &= mask - 1;
+        Some(cell(bit))
+    })
+}
+
+// splitmix64, to fill the Zobrist tables at compile time
+const fn splitmix64(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    (state, z ^ (z >> 31))
+}
+
+// a random key for every player on every bit of the grid, indexed [bit][player]
+static ZOBRIST_CELLS: [[u64; 2]; 128] = {
+    let mut keys = [[0; 2]; 128];
+    let mut state = 0x2545f4914f6cdd1d;
+    let mut i = 0;
+    while i < 128 * 2 {
+        let (next, key) = splitmix64(state);
+        state = next;
+        keys[i / 2][i % 2] = key;
+        i += 1;
+    }
+    keys
+};
+
+// mixed in while black is to move
+const ZOBRIST_BLACK_TO_MOVE: u64 = splitmix64(0x6a09e667f3bcc908).1;
+
+// the keys of every marble in `mask` of `player`
+fn zobrist_keys(mut mask: u128, player: Player) -> u64 {
+    let index = match player {
+        Player::White => 0,
+        Player::Black => 1,
+    };
+    let mut hash = 0;
+
+    while mask != 0 {
+        hash ^= ZOBRIST_CELLS[mask.trailing_zeros() as usize][index];
+        mask &= mask - 1;
+    }
+
+    hash
+}
+
+fn side_key(player: Player) -> u64 {
+    match player {
+        Player::White => 0,
+        Player::Black => ZOBRIST_BLACK_TO_MOVE,
+    }
+}
+
+#[derive(Clone, Debug, Copy, PartialEq, Eq)]
 pub enum Space {
     Occupied(Player),
     Empty,
     OutOfBounds,
 }
 
-#[derive(Clone, Debug, Copy)]
+#[derive(Clone, Debug, Copy, PartialEq, Eq)]
 pub enum Player {
     Black,
     White,
 }
 
+impl Player {
+    pub fn opponent(self) -> Player {
+        match self {
+            Player::White => Player::Black,
+            Player::Black => Player::White,
+        }
+    }
+}
+
+// Two games are equal when the same marbles stand on the same cells with the same player
+// to move, however they got there. The marbles are private bitboards: read them with `space`
+// or `board`, which copies them out into a [x][y] array, and change them with `set`, which
+// keeps `hash` right.
 #[derive(Clone, Debug, Copy)]
 pub struct Game {
-    pub board: [[Space; 9]; 9],
+    white: u128,
+    black: u128,
     pub player: Player,
     pub winner: Option<Player>,
     pub game_over: bool,
     pub move_number: i16,
     pub white_pieces: i16,
-    pub black_pieces: i16
+    pub black_pieces: i16,
+    // Zobrist hash of the marbles alone, kept up to date by `set` and `make_move`
+    board_hash: u64,
+}
+
+impl PartialEq for Game {
+    fn eq(&self, other: &Self) -> bool {
+        self.board_hash == other.board_hash
+            && self.player == other.player
+            && self.white == other.white
+            && self.black == other.black
+    }
+}
+
+impl Eq for Game {}
+
+impl std::hash::Hash for Game {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash());
+    }
 }
 
 impl Game {
     pub fn new_game() -> Self {
-        let mut game = Self {
-            board: [[Space::Empty; 9]; 9],
+        Self {
+            white: 0,
+            black: 0,
             player: Player::White,
             winner: None,
             move_number: 0,
             game_over: false,
             white_pieces  : 0,
             black_pieces : 0,
-        };
+            board_hash: 0,
+        }
+    }
 
-        for x in 0..9 {
-            for y in 0..9 {
-                if y < 4 && x > 4 + y {
-                    game.board[x][y] = Space::OutOfBounds;
-                } else if y > 4 && x < y - 4 {
-                    game.board[x][y] = Space::OutOfBounds;
-                }
-            }
+    // what stands on cell (x, y) of the 9 by 9 array
+    pub fn space(&self, x: usize, y: usize) -> Space {
+        if x >= 9 || y >= 9 {
+            return Space::OutOfBounds;
         }
 
-        game
+        let mask = 1 << bit(x, y);
+        if CELLS & mask == 0 {
+            Space::OutOfBounds
+        } else if self.white & mask != 0 {
+            Space::Occupied(Player::White)
+        } else if self.black & mask != 0 {
+            Space::Occupied(Player::Black)
+        } else {
+            Space::Empty
+        }
     }
 
-    pub fn new_basic() -> Self {
-        let mut game = Self::new_game();
-
-        let pieces = vec![
-            (0, 3),
-            (0, 4),
-            (1, 4),
-            (1, 5),
-            (2, 4),
-            (2, 5),
-            (2, 6),
-            (3, 5),
-            (3, 6),
-            (3, 7),
-            (4, 6),
-            (4, 7),
-            (4, 8),
-            (5, 8),
-        ];
-
-        game.white_pieces = 14;
-        game.black_pieces = 14;
-
-        for (x, y) in pieces {
-            game.board[y][x] = Space::Occupied(Player::White);
-            game.board[x][y] = Space::Occupied(Player::Black);
+    // the whole board as an array indexed [x][y]
+    pub fn board(&self) -> [[Space; 9]; 9] {
+        let mut board = [[Space::OutOfBounds; 9]; 9];
+
+        for (x, column) in board.iter_mut().enumerate() {
+            for (y, space) in column.iter_mut().enumerate() {
+                *space = self.space(x, y);
+            }
         }
-        game
+
+        board
     }
 
-    pub fn new_german_daisy() -> Self {
-        let mut game = Self::new_game();
-
-        let white_pieces = vec![
-            (0, 2),
-            (0, 3),
-            (1, 2),
-            (1, 3),
-            (1, 4),
-            (2, 3),
-            (2, 4),
-            (6, 5),
-            (6, 4),
-            (7, 6),
-            (7, 5),
-            (7, 4),
-            (8, 6),
-            (8, 5),
-        ];
-
-        let black_pieces = vec![
-            (2, 1),
-            (2, 0),
-            (3, 2),
-            (3, 1),
-            (3, 0),
-            (4, 2),
-            (4, 1),
-            (4, 6),
-            (4, 7),
-            (5, 6),
-            (5, 7),
-            (5, 8),
-            (6, 7),
-            (6, 8),
-        ];
-
-        game.white_pieces = 14;
-        game.black_pieces = 14;
-
-        for (x, y) in white_pieces {
-            game.board[y][x] = Space::Occupied(Player::White);
+    // Puts `space` on a cell, keeping the hash up to date. Cells off the board stay off it,
+    // whatever is put there.
+    pub fn set(&mut self, x: usize, y: usize, space: Space) {
+        if x >= 9 || y >= 9 {
+            return;
         }
 
-        for (x, y) in black_pieces {
-            game.board[y][x] = Space::Occupied(Player::Black);
+        let mask = (1 << bit(x, y)) & CELLS;
+        self.board_hash ^=
+            zobrist_keys(self.white & mask, Player::White) ^ zobrist_keys(self.black & mask, Player::Black);
+        self.white &= !mask;
+        self.black &= !mask;
+
+        if let Space::Occupied(player) = space {
+            self.board_hash ^= zobrist_keys(mask, player);
+            match player {
+                Player::White => self.white |= mask,
+                Player::Black => self.black |= mask,
+            }
         }
+    }
 
-        game
+    // Zobrist hash of the board and the player to move
+    pub fn hash(&self) -> u64 {
+        self.board_hash ^ side_key(self.player)
     }
 
-    pub fn new_belgian_daisy() -> Self {
-        let mut game = Self::new_game();
-
-        let white_pieces = vec![
-            (0, 3),
-            (0, 4),
-            (1, 3),
-            (1, 4),
-            (1, 5),
-            (2, 4),
-            (2, 5),
-            (6, 4),
-            (6, 3),
-            (7, 5),
-            (7, 4),
-            (7, 3),
-            (8, 5),
-            (8, 4),
-        ];
-
-        let black_pieces = vec![
-            (4, 1),
-            (3, 0),
-            (3, 1),
-            (4, 2),
-            (4, 1),
-            (4, 0),
-            (5, 2),
-            (5, 1),
-            (3, 6),
-            (3, 7),
-            (4, 6),
-            (4, 7),
-            (4, 8),
-            (5, 7),
-            (5, 8),
-        ];
-
-        game.white_pieces = 14;
-        game.black_pieces = 14;
-
-        for (x, y) in white_pieces {
-            game.board[y][x] = Space::Occupied(Player::White);
-        }
+    // the hash computed from scratch, which `hash` always equals
+    pub fn zobrist(&self) -> u64 {
+        side_key(self.player) ^ zobrist_keys(self.white, Player::White) ^ zobrist_keys(self.black, Player::Black)
+    }
 
-        for (x, y) in black_pieces {
-            game.board[y][x] = Space::Occupied(Player::Black);
+    // the marbles of the player to move and of their opponent
+    fn sides(&self) -> (u128, u128) {
+        match self.player {
+            Player::White => (self.white, self.black),
+            Player::Black => (self.black, self.white),
         }
+    }
+
+    pub fn new_basic() -> Self {
+        Layout::named("standard").unwrap().game()
+    }
 
-        game
+    pub fn new_german_daisy() -> Self {
+        Layout::named("german-daisy").unwrap().game()
+    }
+
+    pub fn new_belgian_daisy() -> Self {
+        Layout::named("belgian-daisy").unwrap().game()
+    }
+
+    pub fn new_layout(name: &str) -> Option<Self> {
+        Layout::named(name).map(|layout| layout.game())
     }
 
     pub fn get_legal_moves(&self) -> Vec<Move> {
         let mut moves: Vec<Move> = Vec::new();
+        self.for_each_move(|legal_move| moves.push(legal_move));
+        moves
+    }
 
-        for x in 0..9 {
-            for y in 0..9 {
-                // if a space occupied by a player
-                if let Space::Occupied(player) = self.board[x][y] {
-                    if variant_eq(&player, &self.player) {
-                        // Search along all directions
-                        for dir_index in 0..6 {
-                            moves.append(&mut Self::search_along_dir(&self, x, y, dir_index));
-                        }
-                    };
+    // Replaces the contents of `moves` with the legal moves, the same as `get_legal_moves`
+    // but without allocating.
+    pub fn generate_moves(&self, moves: &mut MoveList) {
+        moves.clear();
+        self.for_each_move(|legal_move| moves.push(legal_move));
+    }
+
+    fn for_each_move<F: FnMut(Move)>(&self, mut emit: F) {
+        let (own, theirs) = self.sides();
+        let empty = CELLS & !(own | theirs);
+        // where a pushed marble may go: an empty cell, or off the board
+        let free = !(own | theirs);
+
+        for (dir_index, &dir) in DIRS.iter().enumerate() {
+            // the cells whose neighbour `steps` cells on is in `mask`
+            let behind_by = |mask: u128, steps: usize| (0..steps).fold(mask, |mask, _| behind(mask, dir_index));
+
+            // the rearmost marbles of lines of at least two and three of the mover's marbles
+            let two = own & behind_by(own, 1);
+            let three = two & behind_by(own, 2);
+
+            // a line moving into an empty cell, or pushing a shorter line of opposing marbles
+            // into an empty cell or off the board
+            let inline = (own & behind_by(empty, 1))
+                | (two & behind_by(empty, 2))
+                | (two & behind_by(theirs, 2) & behind_by(free, 3))
+                | (three & behind_by(empty, 3))
+                | (three & behind_by(theirs, 3) & behind_by(free, 4))
+                | (three & behind_by(theirs, 3) & behind_by(theirs, 4) & behind_by(free, 5));
+
+            cells(inline).for_each(|start| emit(Move::Inline { start, dir }));
+
+            // Lines of two or three stepping sideways, every marble into an empty cell. Each
+            // line is found from its rearmost marble in the first three directions only, as the
+            // other three run along the same lines from the other end, and can step to either
+            // side in both of the directions not along it. The opposite of DIRS[i] is DIRS[5 - i].
+            if dir_index >= 3 {
+                continue;
+            }
+            for perp_dir_idx in (0..6).filter(|&perp| perp != dir_index && perp != 5 - dir_index) {
+                let perp_dir = DIRS[perp_dir_idx];
+                let side_empty = behind(empty, perp_dir_idx);
+                let pairs = two & side_empty & behind_by(side_empty, 1);
+                let triples = three & side_empty & behind_by(side_empty, 1) & behind_by(side_empty, 2);
+
+                let offset = |(x, y): (usize, usize), steps: i8| {
+                    ((x as i8 + dir.0 * steps) as usize, (y as i8 + dir.1 * steps) as usize)
                 };
+
+                cells(pairs).for_each(|start| {
+                    emit(Move::Broadside { start, mid: None, stop: offset(start, 1), dir: perp_dir })
+                });
+                cells(triples).for_each(|start| {
+                    emit(Move::Broadside { start, mid: Some(offset(start, 1)), stop: offset(start, 2), dir: perp_dir })
+                });
             }
         }
-        moves
     }
 
-    #[cfg_attr(rustfmt, rustfmt_skip)]
-    fn search_along_dir(&self, x: usize, y: usize, dir_index: usize) -> Vec<Move> {
-        let mut moves: Vec<Move> = Vec::new();
-        let mut sumito = 1;
-        let mut opp_sumito = 0;
+    // the marbles `next_move` moves, which step all together in the direction returned
+    fn moved_marbles(&self, next_move: &Move) -> (u128, usize) {
+        let dir_index = |dir: &(i8, i8)| DIRS.iter().position(|d| d == dir).unwrap();
+        let occupied = self.white | self.black;
 
-        let x_off = DIRS[dir_index].0;
-        let y_off = DIRS[dir_index].1;
-
-        let mut new_x = x as i8 + x_off;
-        let mut new_y = y as i8 + y_off;
+        match next_move {
+            Move::Inline { start, dir } => {
+                let dir_index = dir_index(dir);
+                let mut marble = 1 << bit(start.0, start.1);
+                let mut moved = 0;
 
-        if !in_bounds(new_x, new_y) {
-            return moves;
-        };
+                while marble & occupied != 0 {
+                    moved |= marble;
+                    marble = step(marble, dir_index);
+                }
 
-        match self.board[new_x as usize][new_y as usize] {
-            Space::Empty => {
-                moves.push(Move::Inline {
-                    start: (x, y),
-                    dir: (x_off, y_off),
-                });
-                return moves;
+                (moved, dir_index)
             }
-            Space::OutOfBounds => return moves,
-            _ => (),
-        };
-
-        loop {
-            // if next square not in-bounds, check if you are pushing an opponent's piece off
-            if !in_bounds(new_x, new_y) {
-                if sumito > opp_sumito && opp_sumito != 0 {
-                    moves.push(Move::Inline {
-                        start: (x, y),
-                        dir: (x_off, y_off),
-                    });
-                }
-                return moves;
-            };
-            match self.board[new_x as usize][new_y as usize] {
-                // if next square is occupied, check if it is by you, or not.
-                // if you occupy it, check for broadsides and look ahead to next square
-                // if not, check if you can push opponent
-                Space::Occupied(player) => {
-                    if variant_eq(&player, &self.player) {
-                        if opp_sumito != 0 {
-                            break;
-                        }
-                        sumito += 1;
-                        if sumito > 3 {
-                            break;
-                        }
-
-                        'perp_dir: for new_dir_offset in 1..3 {
-                            let perp_dir_idx: usize = (dir_index + new_dir_offset) % 6;
-
-                            let x_perp_off: i8 = DIRS[perp_dir_idx].0;
-                            let y_perp_off: i8 = DIRS[perp_dir_idx].1;
-
-                            if !in_bounds(new_x + x_perp_off, new_y + y_perp_off) {
-                                continue 'perp_dir;
-                            };
-                            if !variant_eq(&self.board[(new_x + x_perp_off) as usize][(new_y + y_perp_off) as usize], &Space::Empty) {
-                                continue 'perp_dir;
-                            };
-
-                            if !in_bounds(x as i8 + x_perp_off, y as i8 + y_perp_off) {
-                                continue 'perp_dir;
-                            };
-                            if !variant_eq(&self.board[(x as i8 + x_perp_off) as usize][(y as i8 + y_perp_off) as usize], &Space::Empty) {
-                                continue 'perp_dir;
-                            };
-
-                            if sumito == 3 {
-                                let mid_x: i8 = x as i8 + x_off + x_perp_off;
-                                let mid_y: i8 = y as i8 + y_off + y_perp_off;
-
-                                if !in_bounds(mid_x, mid_y) {
-                                    continue 'perp_dir;
-                                };
-                                if !variant_eq(&self.board[mid_x as usize][mid_y as usize], &Space::Empty) {
-                                    continue 'perp_dir;
-                                };
-                                moves.push(Move::Broadside {
-                                    start: (x, y),
-                                    mid: Some((
-                                        (x as i8 + x_off) as usize,
-                                        (y as i8 + y_off) as usize,
-                                    )),
-                                    stop: (new_x as usize, new_y as usize),
-                                    dir: (x_perp_off, y_perp_off),
-                                })
-                            } else {
-                                moves.push(Move::Broadside {
-                                    start: (x, y),
-                                    mid: None,
-                                    stop: (new_x as usize, new_y as usize),
-                                    dir: (x_perp_off, y_perp_off),
-                                })
-                            }
-                        }
-                    } else {
-                        opp_sumito += 1;
-                        if opp_sumito == sumito {
-                            break;
-                        }
-                    }
-                }
-                Space::Empty => {
-                    if sumito > opp_sumito {
-                        moves.push(Move::Inline {
-                            start: (x, y),
-                            dir: (x_off, y_off),
-                        });
-                        break;
-                    }
-                }
-                Space::OutOfBounds => {
-                    if sumito > opp_sumito && opp_sumito != 0 {
-                        moves.push(Move::Inline {
-                            start: (x, y),
-                            dir: (x_off, y_off),
-                        });
-                    }
-                    break;
+            Move::Broadside { start, mid, stop, dir } => {
+                let mut moved = (1 << bit(start.0, start.1)) | (1 << bit(stop.0, stop.1));
+                if let Some(mid) = mid {
+                    moved |= 1 << bit(mid.0, mid.1);
                 }
-            };
-            new_x += x_off;
-            new_y += y_off;
+
+                (moved, dir_index(dir))
+            }
         }
-        return moves;
     }
 
     pub fn make_move(&self, next_move: &Move) -> Self {
-        let mut new_state = self.clone();
+        let mut new_state = *self;
 
         new_state.move_number += 1;
 
-        new_state.player = match self.player {
-            Player::White => Player::Black,
-            Player::Black => Player::White,
-        };
+        new_state.player = self.player.opponent();
 
-        match next_move {
-            Move::Inline { start, dir } => {
-                let mut x = start.0 as i8;
-                let mut y = start.1 as i8;
-                let mut new_x = x + dir.0;
-                let mut new_y = y + dir.1;
-
-                new_state.board[x as usize][y as usize] = Space::Empty;
-                new_state.board[new_x as usize][new_y as usize] = self.board[start.0][start.1];
-
-                x = new_x;
-                y = new_y;
-                new_x = x + dir.0;
-                new_y = y + dir.1;
-
-                while matches!(self.board[x as usize][y as usize], Space::Occupied(_)) {
-
-                    //if I would be moving to an out of bounds, don't.
-                    // Instead, knock piece off
-                    if !in_bounds(new_x, new_y)  || matches!(self.board[new_x as usize][new_y as usize], Space::OutOfBounds) {
-                        match self.board[x as usize][y as usize]{
-                            Space::Occupied(moved_off) => {
-                                match moved_off {
-                                    Player::White => {new_state.white_pieces -= 1},
-                                    Player::Black => {new_state.black_pieces -= 1},
-                                };
-                            },
-                            _=> panic!("{:?}", self.board[x as usize][y as usize]),
-                        };
-                        break
-                    };
-
-
-                    new_state.board[new_x as usize][new_y as usize] = self.board[x as usize][y as usize];
-                    x = new_x;
-                    y = new_y;
-                    new_x = x + dir.0;
-                    new_y = y + dir.1;
-                }
-            }
-            Move::Broadside {
-                start,
-                mid,
-                stop,
-                dir,
-            } => {
-                new_state.board[start.0][start.1] = Space::Empty;
-                new_state.board[stop.0][stop.1] = Space::Empty;
-
-                new_state.board[(start.0 as i8 + dir.0) as usize]
-                    [(start.1 as i8 + dir.1) as usize] = self.board[start.0][start.1];
-                new_state.board[(stop.0 as i8 + dir.0) as usize][(stop.1 as i8 + dir.1) as usize] =
-                    self.board[stop.0][stop.1];
+        // marbles stepping off the board are gone
+        let (moved, dir) = self.moved_marbles(next_move);
+        new_state.white = (self.white & !moved) | (step(self.white & moved, dir) & CELLS);
+        new_state.black = (self.black & !moved) | (step(self.black & moved, dir) & CELLS);
+        new_state.white_pieces = new_state.white.count_ones() as i16;
+        new_state.black_pieces = new_state.black.count_ones() as i16;
 
-                if let Some(mid) = mid {
-                    new_state.board[mid.0][mid.1] = Space::Empty;
-                    new_state.board[(mid.0 as i8 + dir.0) as usize]
-                        [(mid.1 as i8 + dir.1) as usize] = self.board[mid.0][mid.1];
-                };
-            }
-        };
+        new_state.board_hash ^= zobrist_keys(self.white ^ new_state.white, Player::White)
+            ^ zobrist_keys(self.black ^ new_state.black, Player::Black);
 
         if new_state.white_pieces == 8 {
             new_state.winner = Some(Player::Black);
@@ -450,7 +429,7 @@ impl Game {
 
         for y in 0..9 {
             for x in 0..9 {
-                match self.board[x][y] {
+                match self.space(x, y) {
                     Space::Occupied(Player::Black) => black_seen += 1,
                     Space::Occupied(Player::White) => white_seen +=1,
                     _ => (),
@@ -467,59 +446,6 @@ impl Game {
         }
 
     }
-
-    pub fn random_playout(&self) -> Option<Player> {
-        let next_move : Move = * Game::get_legal_moves(self)
-            .choose(&mut rand::thread_rng())
-            .unwrap();
-
-        let next_state : Game = Game::make_move(self, &next_move);
-
-        if next_state.game_over {
-            return next_state.winner
-        } else {
-            return next_state.random_playout()
-        }
-    }
-
-    pub fn greedy_playout(&self) -> Option<Player> {
-        let next_moves : Vec<Move> = Game::get_legal_moves(self);
-
-        let current_black = self.black_pieces;
-        let current_white = self.white_pieces;
-
-        let next_state: Game;
-        for potenital_move in &next_moves{
-            let potential_state : Game = Game::make_move(self, potenital_move);
-
-            let next_black = potential_state.black_pieces;
-            let next_white = potential_state.white_pieces;
-
-            if next_black < current_black || next_white < current_white {
-                next_state = potential_state;
-
-
-                if next_state.game_over {
-                    return next_state.winner
-                } else {
-                    return next_state.greedy_playout()
-                };
-            };
-        };
-
-
-        let next_move: Move = * next_moves
-            .choose(&mut rand::thread_rng())
-            .unwrap();
-
-        next_state = Game::make_move(self, &next_move);
-
-        if next_state.game_over {
-            return next_state.winner
-        } else {
-            return next_state.greedy_playout()
-        };
-    }
 }
 
 impl std::fmt::Display for Game {
@@ -527,7 +453,7 @@ impl std::fmt::Display for Game {
         let mut output = String::new();
         for y in 0..9 {
             for x in 0..9 {
-                match self.board[x][y] {
+                match self.space(x, y) {
                     Space::Empty => output.push('_'),
                     Space::Occupied(Player::Black) => output.push('B'),
                     Space::Occupied(Player::White) => output.push('W'),
@@ -569,3 +495,75 @@ pub enum Move {
         dir: (i8, i8),
     },
 }
+
+// a broadside can be described from either end of the line, so both orderings are the same move
+impl PartialEq for Move {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Move::Inline { start: a, dir: a_dir }, Move::Inline { start: b, dir: b_dir }) => {
+                a == b && a_dir == b_dir
+            }
+            (
+                Move::Broadside { start: a_start, mid: a_mid, stop: a_stop, dir: a_dir },
+                Move::Broadside { start: b_start, mid: b_mid, stop: b_stop, dir: b_dir },
+            ) => {
+                a_dir == b_dir
+                    && a_mid == b_mid
+                    && ((a_start == b_start && a_stop == b_stop)
+                        || (a_start == b_stop && a_stop == b_start))
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Move {}
+
+// Every marble of a side can make at most one inline move in each of the six directions,
+// and head at most one line of two and one of three along each of the three axes, each of
+// which can step sideways four ways. A side never has more than 14 marbles.
+pub const MAX_MOVES: usize = 14 * (6 + 3 * 2 * 4);
+
+// A fixed capacity list of moves, for generating moves without allocating. Reuse one list
+// across many positions: making a new one fills all `MAX_MOVES` slots.
+#[derive(Clone)]
+pub struct MoveList {
+    moves: [Move; MAX_MOVES],
+    len: usize,
+}
+
+impl MoveList {
+    pub fn new() -> Self {
+        MoveList { moves: [Move::Inline { start: (0, 0), dir: (0, 0) }; MAX_MOVES], len: 0 }
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    // panics once the list holds `MAX_MOVES` moves
+    pub fn push(&mut self, next_move: Move) {
+        self.moves[self.len] = next_move;
+        self.len += 1;
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for MoveList {
+    type Target = [Move];
+
+    fn deref(&self) -> &[Move] {
+        &self.moves[..self.len]
+    }
+}
+
+impl fmt::Debug for MoveList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
@@ -0,0 +1,31 @@
+//! The plain text format layouts and evaluation weights are loaded from: one `name = value`
+//! setting per line, with blank lines and lines starting with `#` ignored.
+
+use std::fmt;
+
+// A line that is neither a setting, a comment nor blank
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidLine {
+    pub line: usize,
+    pub text: String,
+}
+
+impl fmt::Display for InvalidLine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: expected name = value, found {:?}", self.line, self.text)
+    }
+}
+
+impl std::error::Error for InvalidLine {}
+
+// The settings in `s` as their line number, name and value, both trimmed
+pub fn parse(s: &str) -> impl Iterator<Item = Result<(usize, &str, &str), InvalidLine>> {
+    s.lines()
+        .enumerate()
+        .map(|(index, text)| (index + 1, text.trim()))
+        .filter(|(_, text)| !text.is_empty() && !text.starts_with('#'))
+        .map(|(line, text)| match text.split_once('=') {
+            Some((name, value)) => Ok((line, name.trim(), value.trim())),
+            None => Err(InvalidLine { line, text: text.to_string() }),
+        })
+}
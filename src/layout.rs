@@ -0,0 +1,328 @@
+//! Starting layouts, written down as the cells each side starts on in standard notation.
+//!
+//! A layout is checked when it is built: every cell must be on the board and given only
+//! once, and each side must start with the usual 14 marbles. The built in layouts are also
+//! checked to be fair, with black's marbles a rotation or reflection of white's.
+//!
+//! Other layouts can be loaded from a file in the `keyvalue` format, giving the layout's name
+//! and the cells of each side, separated by spaces or commas:
+//!
+//! ```text
+//! # the pyramid, one row short of a full triangle
+//! name = pyramid
+//! white = A1 A2 A3 A4 A5 B1 B2 B3 B4 C1 C2 C3 D1 D2
+//! black = I5 I6 I7 I8 I9 H6 H7 H8 H9 G7 G8 G9 F8 F9
+//! ```
+//!
+//! A loaded layout does not have to be symmetric, so that lopsided openings can be tried.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::game::{Game, Player, Space};
+use crate::keyvalue::{self, InvalidLine};
+use crate::notation::{cell_name, parse_cell};
+use crate::position::STARTING_PIECES;
+
+#[derive(Debug)]
+pub enum LayoutError {
+    Io(io::Error),
+    InvalidLine(InvalidLine),
+    UnknownKey { line: usize, name: String },
+    UnknownLayout(String),
+    InvalidCell(String),
+    Overlap(String),
+    WrongCount { player: Player, count: usize },
+    Asymmetric,
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LayoutError::Io(error) => write!(f, "{}", error),
+            LayoutError::InvalidLine(error) => write!(f, "{}", error),
+            LayoutError::UnknownKey { line, name } => {
+                write!(f, "line {}: unknown key {:?}, expected name, white or black", line, name)
+            }
+            LayoutError::UnknownLayout(name) => {
+                let names: Vec<&str> = Layout::built_in_names().collect();
+                write!(f, "no layout or layout file called {:?}, the built in ones are {}", name, names.join(", "))
+            }
+            LayoutError::InvalidCell(cell) => write!(f, "{:?} is not a cell on the board", cell),
+            LayoutError::Overlap(cell) => write!(f, "{} is given more than once", cell),
+            LayoutError::WrongCount { player, count } => {
+                write!(f, "{:?} has {} marbles, expected {}", player, count, STARTING_PIECES)
+            }
+            LayoutError::Asymmetric => write!(f, "black's marbles are not a rotation or reflection of white's"),
+        }
+    }
+}
+
+impl std::error::Error for LayoutError {}
+
+// I/O errors have no equality of their own, so they are compared by kind
+impl PartialEq for LayoutError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (LayoutError::Io(a), LayoutError::Io(b)) => a.kind() == b.kind(),
+            (LayoutError::InvalidLine(a), LayoutError::InvalidLine(b)) => a == b,
+            (LayoutError::UnknownKey { line: a, name: x }, LayoutError::UnknownKey { line: b, name: y }) => {
+                a == b && x == y
+            }
+            (LayoutError::UnknownLayout(a), LayoutError::UnknownLayout(b)) => a == b,
+            (LayoutError::InvalidCell(a), LayoutError::InvalidCell(b)) => a == b,
+            (LayoutError::Overlap(a), LayoutError::Overlap(b)) => a == b,
+            (
+                LayoutError::WrongCount { player: a, count: x },
+                LayoutError::WrongCount { player: b, count: y },
+            ) => a == b && x == y,
+            (LayoutError::Asymmetric, LayoutError::Asymmetric) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for LayoutError {}
+
+impl From<io::Error> for LayoutError {
+    fn from(error: io::Error) -> Self {
+        LayoutError::Io(error)
+    }
+}
+
+impl From<InvalidLine> for LayoutError {
+    fn from(error: InvalidLine) -> Self {
+        LayoutError::InvalidLine(error)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    pub name: String,
+    white: Vec<(usize, usize)>,
+    black: Vec<(usize, usize)>,
+}
+
+// a built in layout, under the names it can be asked for by
+struct Definition {
+    names: &'static [&'static str],
+    white: [&'static str; 14],
+    black: [&'static str; 14],
+}
+
+const BUILT_IN: [Definition; 9] = [
+    Definition {
+        names: &["standard", "basic"],
+        white: ["A1", "A2", "A3", "A4", "A5", "B1", "B2", "B3", "B4", "B5", "B6", "C3", "C4", "C5"],
+        black: ["I5", "I6", "I7", "I8", "I9", "H4", "H5", "H6", "H7", "H8", "H9", "G5", "G6", "G7"],
+    },
+    Definition {
+        names: &["german-daisy", "german_daisy"],
+        white: ["B1", "B2", "C1", "C2", "C3", "D2", "D3", "F7", "F8", "G7", "G8", "G9", "H8", "H9"],
+        black: ["B5", "B6", "C5", "C6", "C7", "D6", "D7", "F3", "F4", "G3", "G4", "G5", "H4", "H5"],
+    },
+    Definition {
+        names: &["belgian-daisy", "belgian_daisy"],
+        white: ["A1", "A2", "B1", "B2", "B3", "C2", "C3", "G7", "G8", "H7", "H8", "H9", "I8", "I9"],
+        black: ["A4", "A5", "B4", "B5", "B6", "C5", "C6", "G4", "G5", "H4", "H5", "H6", "I5", "I6"],
+    },
+    // the Belgian daisies with their centre marbles swapped
+    Definition {
+        names: &["swiss-daisy", "swiss_daisy"],
+        white: ["A1", "A2", "B1", "B3", "C2", "C3", "G7", "G8", "H7", "H9", "I8", "I9", "B5", "H5"],
+        black: ["A4", "A5", "B4", "B6", "C5", "C6", "G4", "G5", "H4", "H6", "I5", "I6", "B2", "H8"],
+    },
+    // the German daisies with their centre marbles swapped
+    Definition {
+        names: &["dutch-daisy", "dutch_daisy"],
+        white: ["B1", "B2", "C1", "C3", "D2", "D3", "F7", "F8", "G7", "G9", "H8", "H9", "C6", "G4"],
+        black: ["B5", "B6", "C5", "C7", "D6", "D7", "F3", "F4", "G3", "G5", "H4", "H5", "C2", "G8"],
+    },
+    Definition {
+        names: &["domination"],
+        white: ["A2", "A3", "A4", "B2", "B3", "B4", "B5", "C2", "C3", "C4", "C5", "C6", "D4", "D5"],
+        black: ["I6", "I7", "I8", "H5", "H6", "H7", "H8", "G4", "G5", "G6", "G7", "G8", "F5", "F6"],
+    },
+    Definition {
+        names: &["pyramid"],
+        white: ["A1", "A2", "A3", "A4", "A5", "B1", "B2", "B3", "B4", "C1", "C2", "C3", "D1", "D2"],
+        black: ["I5", "I6", "I7", "I8", "I9", "H6", "H7", "H8", "H9", "G7", "G8", "G9", "F8", "F9"],
+    },
+    // a group of each side in every corner
+    Definition {
+        names: &["alliances"],
+        white: ["A1", "A2", "B1", "B2", "C2", "D7", "D8", "E8", "E9", "F9", "H4", "H5", "I5", "I6"],
+        black: ["A4", "A5", "B5", "B6", "C6", "D1", "D2", "E1", "E2", "F2", "H8", "H9", "I8", "I9"],
+    },
+    Definition {
+        names: &["snakes"],
+        white: ["A1", "A2", "A3", "A4", "A5", "B6", "C7", "D8", "D7", "D6", "D5", "D4", "C3", "B2"],
+        black: ["I9", "I8", "I7", "I6", "I5", "H4", "G3", "F2", "F3", "F4", "F5", "F6", "G7", "H8"],
+    },
+];
+
+// Cube coordinates centred on E5, in which the twelve symmetries of the board are
+// rotations of the axes and swaps of two of them.
+fn to_cube((x, y): (usize, usize)) -> (i8, i8, i8) {
+    let (x, y) = (x as i8, y as i8);
+    (x - 4, 4 - y, y - x)
+}
+
+fn from_cube((a, b, _): (i8, i8, i8)) -> (usize, usize) {
+    ((a + 4) as usize, (4 - b) as usize)
+}
+
+fn sorted(cells: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut cells = cells.to_vec();
+    cells.sort_unstable();
+    cells
+}
+
+impl Layout {
+    pub fn new(name: &str, white: &[&str], black: &[&str]) -> Result<Self, LayoutError> {
+        let parse = |cells: &[&str]| {
+            cells
+                .iter()
+                .map(|cell| parse_cell(cell).map_err(|_| LayoutError::InvalidCell(cell.to_string())))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let layout = Layout { name: name.to_string(), white: parse(white)?, black: parse(black)? };
+
+        let mut seen = Vec::new();
+        for &cell in layout.white.iter().chain(&layout.black) {
+            if seen.contains(&cell) {
+                return Err(LayoutError::Overlap(cell_name(cell)));
+            }
+            seen.push(cell);
+        }
+
+        for (player, cells) in [(Player::White, &layout.white), (Player::Black, &layout.black)] {
+            if cells.len() != STARTING_PIECES as usize {
+                return Err(LayoutError::WrongCount { player, count: cells.len() });
+            }
+        }
+
+        Ok(layout)
+    }
+
+    // a layout that must also be fair to both sides
+    pub fn symmetric(name: &str, white: &[&str], black: &[&str]) -> Result<Self, LayoutError> {
+        let layout = Self::new(name, white, black)?;
+        if !layout.is_symmetric() {
+            return Err(LayoutError::Asymmetric);
+        }
+        Ok(layout)
+    }
+
+    // the built in layout going by `name`
+    pub fn named(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        let definition = BUILT_IN.iter().find(|definition| definition.names.contains(&name.as_str()))?;
+
+        let layout = Self::symmetric(definition.names[0], &definition.white, &definition.black);
+        Some(layout.unwrap_or_else(|error| panic!("the built in {} layout is invalid: {}", definition.names[0], error)))
+    }
+
+    // The built in layout going by `name`, or failing that the layout in the file at that
+    // path. This is how layouts are chosen wherever a game is started.
+    pub fn find(name: &str) -> Result<Self, LayoutError> {
+        if let Some(layout) = Self::named(name) {
+            return Ok(layout);
+        }
+        if !Path::new(name).is_file() {
+            return Err(LayoutError::UnknownLayout(name.to_string()));
+        }
+        Self::load(name)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, LayoutError> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    // the main name of every built in layout
+    pub fn built_in_names() -> impl Iterator<Item = &'static str> {
+        BUILT_IN.iter().map(|definition| definition.names[0])
+    }
+
+    pub fn white(&self) -> &[(usize, usize)] {
+        &self.white
+    }
+
+    pub fn black(&self) -> &[(usize, usize)] {
+        &self.black
+    }
+
+    // whether some rotation or reflection of the board takes white's marbles onto black's
+    pub fn is_symmetric(&self) -> bool {
+        let black = sorted(&self.black);
+        let rotate = |(a, b, c): (i8, i8, i8)| (-c, -a, -b);
+        let reflect = |(a, b, c): (i8, i8, i8)| (b, a, c);
+
+        [false, true].into_iter().any(|reflected| {
+            (0..6).any(|turns| {
+                let image: Vec<(usize, usize)> = self
+                    .white
+                    .iter()
+                    .map(|&cell| {
+                        let cube = to_cube(cell);
+                        let cube = if reflected { reflect(cube) } else { cube };
+                        from_cube((0..turns).fold(cube, |cube, _| rotate(cube)))
+                    })
+                    .collect();
+                sorted(&image) == black
+            })
+        })
+    }
+
+    // a new game from this layout, white to move
+    pub fn game(&self) -> Game {
+        let mut game = Game::new_game();
+
+        for &(x, y) in &self.white {
+            game.set(x, y, Space::Occupied(Player::White));
+        }
+        for &(x, y) in &self.black {
+            game.set(x, y, Space::Occupied(Player::Black));
+        }
+        game.white_pieces = self.white.len() as i16;
+        game.black_pieces = self.black.len() as i16;
+
+        game
+    }
+}
+
+impl FromStr for Layout {
+    type Err = LayoutError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut name = "custom";
+        let (mut white, mut black) = (Vec::new(), Vec::new());
+
+        for setting in keyvalue::parse(s) {
+            let (line, key, value) = setting?;
+            let cells = || value.split(|c: char| c == ',' || c.is_whitespace()).filter(|cell| !cell.is_empty());
+
+            match key {
+                "name" => name = value,
+                "white" => white = cells().collect(),
+                "black" => black = cells().collect(),
+                key => return Err(LayoutError::UnknownKey { line, name: key.to_string() }),
+            }
+        }
+
+        Self::new(name, &white, &black)
+    }
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names = |cells: &[(usize, usize)]| cells.iter().map(|&cell| cell_name(cell)).collect::<Vec<_>>().join(" ");
+
+        writeln!(f, "name = {}", self.name)?;
+        writeln!(f, "white = {}", names(&self.white))?;
+        writeln!(f, "black = {}", names(&self.black))
+    }
+}
@@ -0,0 +1,34 @@
+pub mod alphabeta;
+pub mod clock;
+pub mod eval;
+pub mod game;
+pub mod keyvalue;
+pub mod layout;
+pub mod node;
+pub mod notation;
+pub mod parallel;
+pub mod perft;
+pub mod play;
+pub mod playout;
+pub mod position;
+pub mod protocol;
+pub mod record;
+pub mod search;
+pub mod selection;
+pub mod transposition;
+
+pub use crate::alphabeta::{alphabeta_search, AlphaBetaResult};
+pub use crate::clock::{Clock, TimeControl, TimeManager};
+pub use crate::eval::{Weights, WeightsError};
+pub use crate::game::{Game, Move, MoveList, Player, Space};
+pub use crate::layout::{Layout, LayoutError};
+pub use crate::node::{ArenaTree, Node};
+pub use crate::notation::ParseMoveError;
+pub use crate::parallel::{shared_tree_search_with_limits, SharedTree};
+pub use crate::perft::{divide, perft};
+pub use crate::playout::{PlayoutKind, PlayoutPolicy};
+pub use crate::position::ParsePositionError;
+pub use crate::record::{GameRecord, GameResult, RecordError};
+pub use crate::search::{mcts_search, parallel_mcts_search, Policies, SearchLimits};
+pub use crate::selection::{SelectionKind, SelectionPolicy};
+pub use crate::transposition::TranspositionTable;
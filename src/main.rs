@@ -1,75 +1,544 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use rand::seq::SliceRandom;
-use rayon::prelude::*;
-use reduce::Reduce;
 
-pub mod game;
-use crate::game::Game;
-use crate::game::Player;
-use crate::game::Move;
+use abalone::ArenaTree;
+use abalone::Game;
+use abalone::GameRecord;
+use abalone::GameResult;
+use abalone::Layout;
+use abalone::Weights;
+use abalone::{Clock, TimeControl, TimeManager};
+use abalone::Move;
+use abalone::Player;
+use abalone::parallel::shared_tree_search_with_limits;
+use abalone::alphabeta::alphabeta_search_with_limits;
+use abalone::search::{Algorithm, Policies, SearchLimits};
+use abalone::playout::PlayoutKind;
+use abalone::selection::SelectionKind;
+use abalone::play::PlaySettings;
+
+#[derive(Parser)]
+#[command(name = "abalone", about = "An MCTS and alpha-beta engine for Abalone")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
 
-pub mod node;
-use crate::node::ArenaTree;
+#[derive(Subcommand)]
+enum Command {
+    /// Play against the engine in the terminal
+    Play {
+        /// The side you play, white moves first
+        #[arg(long, value_enum, default_value_t = Side::White)]
+        side: Side,
+        /// A built in layout such as german-daisy or snakes, or a layout file
+        #[arg(long, default_value = "standard")]
+        layout: String,
+        /// Iterations per move, shared by all search threads
+        #[arg(long, default_value_t = 1000)]
+        think: i32,
+        /// Search for this many milliseconds per move instead of a fixed number of iterations
+        #[arg(long)]
+        movetime: Option<u64>,
+        /// Play with a clock such as 300+5, 600+30x3 or 10/move, replacing --think and --movetime
+        #[arg(long)]
+        clock: Option<TimeControl>,
+        #[arg(long, default_value_t = 7)]
+        threads: usize,
+        #[command(flatten)]
+        search: EngineArgs,
+        /// Append the finished game record to this file
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Let the engine play against itself
+    Selfplay {
+        #[arg(long, default_value_t = 1)]
+        games: usize,
+        /// A built in layout such as german-daisy or snakes, or a layout file
+        #[arg(long, default_value = "standard")]
+        layout: String,
+        #[arg(long, default_value_t = 1000)]
+        think: i32,
+        /// Search for this many milliseconds per move instead of a fixed number of iterations
+        #[arg(long)]
+        movetime: Option<u64>,
+        /// Play with a clock such as 300+5, 600+30x3 or 10/move, replacing --think and --movetime
+        #[arg(long)]
+        clock: Option<TimeControl>,
+        #[arg(long, default_value_t = 7)]
+        threads: usize,
+        #[command(flatten)]
+        search: EngineArgs,
+        /// Play black with this engine instead, to pit the two against each other
+        #[arg(long, value_enum)]
+        black_engine: Option<Engine>,
+        /// Append every game record to this file
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Search a position and report the candidate moves
+    Analyze {
+        /// Position in the format described in `abalone::position`
+        position: String,
+        #[arg(long, default_value_t = 1000)]
+        think: i32,
+        /// Search for this many milliseconds per move instead of a fixed number of iterations
+        #[arg(long)]
+        movetime: Option<u64>,
+        #[arg(long, default_value_t = 7)]
+        threads: usize,
+        #[command(flatten)]
+        search: EngineArgs,
+    },
+    /// Speak the Abalone Engine Protocol on stdin and stdout
+    Aep,
+    /// Count the move sequences of each length up to a depth, to check move generation
+    Perft {
+        /// Position in the format described in `abalone::position`, instead of a layout
+        position: Option<String>,
+        /// A built in layout such as german-daisy or snakes, or a layout file
+        #[arg(long, default_value = "standard")]
+        layout: String,
+        #[arg(long, default_value_t = 3)]
+        depth: usize,
+        /// Break the count at the full depth down by the first move
+        #[arg(long)]
+        divide: bool,
+    },
+    /// Play the engine against a random mover at several think budgets
+    Bench {
+        #[arg(long, default_value_t = 30)]
+        rounds: usize,
+        /// Iteration budgets to compare. With --engine alphabeta the engine plays at --depth instead
+        #[arg(long, value_delimiter = ',', default_values_t = vec![100,200,300,400,500,1000,1500,2500,5000,10000])]
+        think: Vec<i32>,
+        #[arg(long, default_value_t = 7)]
+        threads: usize,
+        #[command(flatten)]
+        search: EngineArgs,
+        /// A built in layout such as german-daisy or snakes, or a layout file
+        #[arg(long, default_value = "standard")]
+        layout: String,
+        /// Write the results as CSV to this file instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+}
 
-fn prompt_user_for_move(game: &Game) -> Game{
-    let legal_moves = game.get_legal_moves();
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Engine {
+    Mcts,
+    Alphabeta,
+}
 
-    for (i, legal_move) in legal_moves.iter().enumerate() {
-        println!("Move {i}: \n {}", game.make_move(&legal_move));
+#[derive(Args)]
+struct EngineArgs {
+    /// The search algorithm
+    #[arg(long, value_enum, default_value_t = Engine::Mcts)]
+    engine: Engine,
+    /// Search depth for alphabeta, used in place of --think
+    #[arg(long, default_value_t = 4)]
+    depth: usize,
+    /// Evaluation weights file for alphabeta, see `abalone::eval` for the format
+    #[arg(long)]
+    weights: Option<PathBuf>,
+    /// How the search picks the child to descend into: ucb1, ucb1-tuned, puct or thompson.
+    /// puct leans towards captures and towards the centre before a move has many visits
+    #[arg(long, default_value = "ucb1")]
+    selection: SelectionKind,
+    /// The exploration constant for ucb1 and puct
+    #[arg(long)]
+    exploration: Option<f32>,
+    /// How playouts choose their moves: random, greedy, heuristic, epsilon-greedy or depth-limited.
+    /// depth-limited plays greedily for 20 moves and calls the game for the side the evaluation favours
+    #[arg(long, default_value = "greedy")]
+    playout: PlayoutKind,
+    /// Cut playouts off after this many moves, calling them for the side with more marbles, or
+    /// the side the evaluation favours when both have as many
+    #[arg(long)]
+    max_playout_length: Option<usize>,
+    /// Share MCTS statistics between transpositions in a table of this many entries, 0 for none
+    #[arg(long, default_value_t = 0)]
+    transposition_entries: usize,
+}
+
+impl EngineArgs {
+    fn weights(&self) -> Weights {
+        match &self.weights {
+            Some(path) => Weights::load(path).unwrap_or_else(|error| {
+                eprintln!("invalid weights file {}: {}", path.display(), error);
+                std::process::exit(1);
+            }),
+            None => Weights::default(),
+        }
     }
 
-    let mut line = String::new();
-    println!("Select Move from above");
-    std::io::stdin().read_line(&mut line).unwrap();
+    fn algorithm(&self, engine: Engine) -> Algorithm {
+        match engine {
+            Engine::Mcts => Algorithm::Mcts(
+                Policies {
+                    selection: self.selection.policy(self.exploration),
+                    playout: self.playout.policy(),
+                    max_playout_length: self.max_playout_length,
+                    transpositions: None,
+                }
+                .with_transpositions(self.transposition_entries),
+            ),
+            Engine::Alphabeta => Algorithm::AlphaBeta(self.weights()),
+        }
+    }
 
-    let i: usize = line.trim().parse().expect("invalid input");
-    println!("selected {:?}", i);
+    fn limits(&self, engine: Engine, think: i32, movetime: Option<u64>) -> SearchLimits {
+        match (movetime, engine) {
+            (Some(millis), _) => SearchLimits::time(Duration::from_millis(millis)),
+            (None, Engine::Mcts) => SearchLimits::iterations(think.max(1) as usize),
+            (None, Engine::Alphabeta) => SearchLimits::iterations(self.depth.max(1)),
+        }
+    }
+}
 
-    game.make_move(&legal_moves[i])
+// one side of a game played by the engine
+struct EnginePlayer {
+    name: String,
+    algorithm: Algorithm,
+    limits: SearchLimits,
 }
 
-fn mcts_search(root: Game, n_think : i32) -> ArenaTree {
-    let mut arena = ArenaTree{nodes : Vec::new()};
+impl EnginePlayer {
+    fn new(
+        engine: Engine,
+        search: &EngineArgs,
+        think: i32,
+        movetime: Option<u64>,
+        clock: Option<TimeControl>,
+        threads: usize,
+    ) -> Self {
+        let limits = search.limits(engine, think, movetime);
 
-    arena.new_node(root, None, None);
+        EnginePlayer {
+            name: engine_name(engine, &limits, clock, threads),
+            algorithm: search.algorithm(engine),
+            limits,
+        }
+    }
+}
 
-    let mut selected: usize;
-    let mut expanded: usize;
-    let mut result: Option<Player>;
+#[derive(Copy, Clone, ValueEnum)]
+enum Side {
+    White,
+    Black,
+}
 
-    for _ in 0..n_think{
-        selected = arena.select_step(0);
-        expanded = arena.expand_step(selected);
-        result = arena.playout(expanded);
-        arena.backpropagate(expanded, result);
+impl Side {
+    fn player(self) -> Player {
+        match self {
+            Side::White => Player::White,
+            Side::Black => Player::Black,
+        }
     }
+}
 
-   arena
+fn starting_layout(name: &str) -> Layout {
+    Layout::find(name).unwrap_or_else(|error| {
+        eprintln!("invalid layout {}: {}", name, error);
+        std::process::exit(1);
+    })
 }
 
-fn main() {
-    println!("n_think,winner,turn_n ");
+fn engine_name(engine: Engine, limits: &SearchLimits, clock: Option<TimeControl>, threads: usize) -> String {
+    if engine == Engine::Alphabeta {
+        return match (clock, limits.time, limits.iterations) {
+            (Some(clock), _, _) => format!("alphabeta at {}", clock),
+            (None, Some(time), _) => format!("alphabeta {}ms", time.as_millis()),
+            (None, None, Some(depth)) => format!("alphabeta depth {}", depth),
+            (None, None, None) => "alphabeta".to_string(),
+        };
+    }
+
+    if let Some(clock) = clock {
+        return format!("mcts on {} threads at {}", threads, clock);
+    }
+
+    match (limits.time, limits.iterations) {
+        (Some(time), _) => format!("mcts {}ms on {} threads", time.as_millis(), threads),
+        (None, Some(iterations)) => format!("mcts {} iterations on {} threads", iterations, threads),
+        (None, None) => format!("mcts on {} threads", threads),
+    }
+}
+
+fn engine_move(game: &Game, limits: &SearchLimits, algorithm: &Algorithm, threads: usize) -> Move {
+    algorithm.search(*game, limits, threads).best_move
+}
+
+// days since the epoch to a calendar date, from Howard Hinnant's `civil_from_days`
+fn today() -> String {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+    let z = secs / 86400 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+fn append_record(path: &PathBuf, record: &GameRecord) {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .expect("could not open output file");
+
+    writeln!(file, "{}", record).expect("could not write game record");
+}
+
+fn play(
+    side: Side,
+    layout: &Layout,
+    engine: EnginePlayer,
+    clock: Option<TimeControl>,
+    threads: usize,
+    output: Option<PathBuf>,
+) {
+    let settings = PlaySettings {
+        human: side.player(),
+        limits: engine.limits,
+        clock,
+        algorithm: engine.algorithm,
+        threads,
+    };
+    let engine = Some(engine.name);
+    let stdin = std::io::stdin();
+
+    let mut record = abalone::play::play(layout.game(), &settings, stdin.lock(), std::io::stdout())
+        .expect("could not talk to the terminal");
+
+    let human = Some("human".to_string());
+    (record.white, record.black) = match settings.human {
+        Player::White => (human, engine),
+        Player::Black => (engine, human),
+    };
+    record.date = Some(today());
+    record.time_control = clock.map(|clock| clock.to_string());
+
+    if let Some(path) = output {
+        append_record(&path, &record);
+    }
+}
+
+fn selfplay(
+    games: usize,
+    layout: &Layout,
+    white: EnginePlayer,
+    black: EnginePlayer,
+    time_control: Option<TimeControl>,
+    threads: usize,
+    output: Option<PathBuf>,
+) {
+    let time_manager = TimeManager::default();
+
+    for _ in 0..games {
+        // nothing learnt in one game carries over to the next
+        let (white_algorithm, black_algorithm) = (white.algorithm.for_new_game(), black.algorithm.for_new_game());
+        let mut game = layout.game();
+        let mut record = GameRecord::new(game);
+        record.white = Some(white.name.clone());
+        record.black = Some(black.name.clone());
+        record.date = Some(today());
+        record.time_control = time_control.map(|control| control.to_string());
 
-    for _ in 0..30 {
-        let think_nums = vec![100,200,300,400,500,1000,1500,2500,5000,10000];
+        let mut clock = time_control.map(Clock::new);
+        // each side searches on from its own tree of the previous move
+        let (mut white_tree, mut black_tree) = (None, None);
 
-        for n_think in think_nums {
-            let mut game = Game::new_basic();
+        while !game.game_over {
+            let (engine, algorithm, tree) = match game.player {
+                Player::White => (&white, &white_algorithm, &mut white_tree),
+                Player::Black => (&black, &black_algorithm, &mut black_tree),
+            };
+            let move_limits = match &clock {
+                Some(clock) => SearchLimits::time(time_manager.allocate(clock, &game)),
+                None => engine.limits.clone(),
+            };
+
+            let started = Instant::now();
+            let next_move = algorithm.search_with_tree(game, &move_limits, threads, tree).best_move;
+
+            if let Some(clock) = &mut clock {
+                if !clock.punch(game.player, started.elapsed()) {
+                    record.result = match game.player {
+                        Player::White => GameResult::BlackWins,
+                        Player::Black => GameResult::WhiteWins,
+                    };
+                    println!("{:?} lost on time", game.player);
+                    break;
+                }
+            }
+
+            game = record.push(next_move).unwrap();
+        }
+        println!("{} in {} moves", record.result, game.move_number);
+
+        if let Some(path) = &output {
+            append_record(path, &record);
+        }
+    }
+}
+
+fn run_perft(position: Option<String>, layout: &Layout, depth: usize, divide: bool) {
+    let game = match position.map(|position| position.parse::<Game>()) {
+        Some(Ok(game)) => game,
+        Some(Err(error)) => {
+            eprintln!("invalid position: {}", error);
+            std::process::exit(1);
+        }
+        None => layout.game(),
+    };
+
+    if divide {
+        let counts = abalone::divide(&game, depth);
+        for (next_move, count) in &counts {
+            println!("{}: {}", next_move, count);
+        }
+        println!("{} moves, {} in all", counts.len(), counts.iter().map(|(_, count)| count).sum::<u64>());
+        return;
+    }
+
+    for depth in 1..=depth {
+        let start = Instant::now();
+        let count = abalone::perft(&game, depth);
+        let elapsed = start.elapsed();
+        println!(
+            "perft {} = {} ({:.0} ms, {:.0} leaves per second)",
+            depth,
+            count,
+            elapsed.as_secs_f64() * 1000.0,
+            count as f64 / elapsed.as_secs_f64().max(1e-9)
+        );
+    }
+}
+
+fn analyze(position: &str, limits: SearchLimits, algorithm: Algorithm, threads: usize, weights: Weights) {
+    let game: Game = match position.parse() {
+        Ok(game) => game,
+        Err(error) => {
+            eprintln!("invalid position: {}", error);
+            std::process::exit(1);
+        }
+    };
+    if game.game_over {
+        println!("the game is over");
+        return;
+    }
+
+    println!("{}", game);
+    println!("static evaluation for {:?}: {}", game.player, weights.evaluate(&game));
+
+    let policies = match algorithm {
+        Algorithm::Mcts(policies) => policies,
+        Algorithm::AlphaBeta(weights) => {
+            let result = alphabeta_search_with_limits(game, &limits, &weights);
+            let line: Vec<String> = result.principal_variation.iter().map(Move::to_string).collect();
+            println!("depth {}, {} nodes, score {}", result.depth, result.progress.nodes, result.score);
+            println!("principal variation: {}", line.join(" "));
+            println!("best move: {}", result.best_move.unwrap());
+            return;
+        }
+    };
+
+    let merged_tree : ArenaTree = shared_tree_search_with_limits(game, &limits, &policies, threads);
+    let root = &merged_tree.nodes[0];
+
+    let mut children: Vec<usize> = (root.first_child_id.unwrap()..root.last_child_id.unwrap()).collect();
+    children.sort_by_key(|&child| -merged_tree.nodes[child].visits);
+
+    println!("move,visits,win_rate");
+    for child in children.into_iter().take(10) {
+        let node = &merged_tree.nodes[child];
+        let win_rate = node.wins as f32 / node.visits.max(1) as f32;
+        println!("{},{},{:.3}", node.move_from_parent.unwrap(), node.visits, win_rate);
+    }
+    println!("best move: {}", merged_tree.nodes[merged_tree.reccomend()].move_from_parent.unwrap());
+}
+
+fn bench(
+    rounds: usize,
+    think_nums: Vec<i32>,
+    search: &EngineArgs,
+    threads: usize,
+    layout: &Layout,
+    output: Option<PathBuf>,
+) {
+    let mut out: Box<dyn Write> = match output {
+        Some(path) => Box::new(std::fs::File::create(path).expect("could not create output file")),
+        None => Box::new(std::io::stdout()),
+    };
+
+    // alpha-beta has no iteration budget, only the depth it was given
+    let think_nums = match search.engine {
+        Engine::Mcts => think_nums,
+        Engine::Alphabeta => vec![search.depth as i32],
+    };
+    writeln!(out, "n_think,winner,turn_n ").unwrap();
+
+    for _ in 0..rounds {
+        for &n_think in &think_nums {
+            // a fresh transposition table, so that no row depends on those before it
+            let algorithm = search.algorithm(search.engine);
+            let limits = search.limits(search.engine, n_think, None);
+            let mut game = layout.game();
             while !game.game_over {
-                // println!("starting turn");
-                let arena_vec : Vec<ArenaTree> = (0..7).
-                    into_par_iter().
-                    map(|_| mcts_search(game, n_think)).
-                    collect();
-                let merged_tree : ArenaTree = Reduce::reduce(arena_vec.into_iter(), | a,b | (&a).merge_trees(&b)).unwrap();
-
-                game = merged_tree.nodes[merged_tree.reccomend()].game_state;
+                game = game.make_move(&engine_move(&game, &limits, &algorithm, threads));
                 game.validate_state();
 
+                if game.game_over {
+                    break;
+                }
 
                 let legal_moves = game.get_legal_moves();
                 game = game.make_move(legal_moves.choose(&mut rand::thread_rng()).unwrap());
             }
-            println!("{}, {:?}, {}",n_think, game.winner.unwrap(), game.move_number);
+            let winner = game.winner.map_or("Draw".to_string(), |winner| format!("{:?}", winner));
+            writeln!(out, "{}, {}, {}",n_think, winner, game.move_number).unwrap();
+        }
+    }
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Command::Play { side, layout, think, movetime, clock, threads, search, output } => {
+            let engine = EnginePlayer::new(search.engine, &search, think, movetime, clock, threads);
+            play(side, &starting_layout(&layout), engine, clock, threads, output)
+        }
+        Command::Selfplay { games, layout, think, movetime, clock, threads, search, black_engine, output } => {
+            let white = EnginePlayer::new(search.engine, &search, think, movetime, clock, threads);
+            let black_engine = black_engine.unwrap_or(search.engine);
+            let black = EnginePlayer::new(black_engine, &search, think, movetime, clock, threads);
+            selfplay(games, &starting_layout(&layout), white, black, clock, threads, output)
+        }
+        Command::Analyze { position, think, movetime, threads, search } => {
+            let limits = search.limits(search.engine, think, movetime);
+            analyze(&position, limits, search.algorithm(search.engine), threads, search.weights())
+        }
+        Command::Aep => abalone::protocol::run(std::io::stdin().lock(), std::io::stdout())
+            .expect("could not talk to the controller"),
+        Command::Perft { position, layout, depth, divide } => {
+            run_perft(position, &starting_layout(&layout), depth, divide)
+        }
+        Command::Bench { rounds, think, threads, search, layout, output } => {
+            bench(rounds, think, &search, threads, &starting_layout(&layout), output)
         }
     }
 }
@@ -1,6 +1,10 @@
 use crate::game::Game;
 use crate::game::Move;
+use crate::game::MoveList;
 use crate::game::Player;
+use crate::playout::PlayoutPolicy;
+use crate::selection::{Candidate, SelectionPolicy};
+use crate::transposition::{NodeStats, TranspositionTable};
 
 use rand::Rng;
 use rand::prelude::IteratorRandom;
@@ -21,8 +25,8 @@ pub struct Node {
     pub game_state: Game,
 }
 
-fn variant_eq<T>(a: &T, b: &T) -> bool {
-    std::mem::discriminant(a) == std::mem::discriminant(b)
+fn same_position(a: &Game, b: &Game) -> bool {
+    a == b && a.move_number == b.move_number
 }
 
 impl ArenaTree {
@@ -64,10 +68,14 @@ impl ArenaTree {
         if self.nodes[node_id].first_child_id.is_none() || self.nodes[node_id].last_child_id.is_none() {
             self.nodes[node_id].first_child_id = Some(self.nodes.len());
 
-            for legal_move in game_state.get_legal_moves() {
-                let next_state: Game = game_state.make_move(&legal_move);
+            let mut legal_moves = MoveList::new();
+            game_state.generate_moves(&mut legal_moves);
+            self.nodes.reserve(legal_moves.len());
 
-                self.new_node(next_state, Some(node_id), Some(legal_move));
+            for legal_move in legal_moves.iter() {
+                let next_state: Game = game_state.make_move(legal_move);
+
+                self.new_node(next_state, Some(node_id), Some(*legal_move));
             }
 
             self.nodes[node_id].last_child_id = Some(self.nodes.len());
@@ -85,61 +93,89 @@ impl ArenaTree {
     }
 
 
-    pub fn select_step(&self, node_id: usize) -> usize {
-        let node = &self.nodes[node_id];
-
-        // if the node has no children, return it for expansion
-        if node.first_child_id.is_none() || node.last_child_id.is_none() {
-            return node_id;
-        };
-
-        let children_start = node.first_child_id.unwrap();
-        let children_stop = node.last_child_id.unwrap();
+    pub fn select_step(&self, node_id: usize, policy: &dyn SelectionPolicy) -> usize {
+        self.select_step_with_table(node_id, policy, None)
+    }
 
-        // if the node is a leaf, return it for expansion
-        if self.is_leaf_node(node_id) {
-            return node_id;
-        } else {
-            let mut scores : Vec<f32> = Vec::new();
+    // Selects with the statistics in `table` where it has them, so children reached by other
+    // lines as well are judged on all their playouts. Untried children are still found by
+    // the visits of their own node.
+    pub fn select_step_with_table(
+        &self,
+        node_id: usize,
+        policy: &dyn SelectionPolicy,
+        table: Option<&TranspositionTable<NodeStats>>,
+    ) -> usize {
+        let mut node_id = node_id;
+        let mut candidates : Vec<Candidate> = Vec::new();
+
+        loop {
+            let node = &self.nodes[node_id];
+
+            // if the node has no children, return it for expansion
+            if node.first_child_id.is_none() || node.last_child_id.is_none() {
+                return node_id;
+            };
 
-            for i in children_start..children_stop {
-                let lhs = self.nodes[i].wins as f32 / self.nodes[i].visits as f32;
-                let rhs = (self.nodes[node_id].visits as f32).log(2.71) / (self.nodes[i].visits as f32);
-                scores.push(lhs + 1.41*(rhs.powf(0.5)));
+            // if the node is a leaf, return it for expansion
+            if self.is_leaf_node(node_id) {
+                return node_id;
             }
 
-            let maxi = scores.iter().enumerate()
-                .fold(
-                    (0, 0.0),
-                    |max, (ind, val)| if val > &max.1 { (ind, *val) } else { max },
-                )
-                .0;
+            let children_start = node.first_child_id.unwrap();
+            let children_stop = node.last_child_id.unwrap();
 
-            self.select_step((children_start..children_stop).nth(maxi).unwrap())
+            candidates.clear();
+            candidates.extend((children_start..children_stop).map(|i| {
+                let child = &self.nodes[i];
+                let stats = NodeStats::lookup(table, &child.game_state, child.visits, child.wins);
+                Candidate { visits: stats.visits as f32, wins: stats.wins as f32, game_state: &child.game_state }
+            }));
+
+            let parent = NodeStats::lookup(table, &node.game_state, node.visits, node.wins);
+            node_id = children_start + policy.select(parent.visits as f32, &candidates);
         }
     }
 
-    pub fn playout(&self, node_id: usize) -> Option<Player> {
+    pub fn playout(&self, node_id: usize, policy: &dyn PlayoutPolicy, max_length: Option<usize>) -> Option<Player> {
         if self.nodes[node_id].game_state.game_over{
             return self.nodes[node_id].game_state.winner
          };
 
-        self.nodes[node_id].game_state.greedy_playout()
+        policy.playout(&self.nodes[node_id].game_state, max_length)
     }
 
     pub fn backpropagate(&mut self, node_id : usize, player : Option<Player>){
-        self.nodes[node_id].visits += 1;
+        let mut node_id = Some(node_id);
+
+        while let Some(id) = node_id {
+            let node = &mut self.nodes[id];
+            node.visits += 1;
 
-        if let Some(winner) = player {
-            if !variant_eq(&winner, &self.nodes[node_id].game_state.player) {
-                self.nodes[node_id].wins += 1;
+            if let Some(winner) = player {
+                if winner != node.game_state.player {
+                    node.wins += 1;
+                };
             };
-        };
 
-        if let Some(id) = self.nodes[node_id].parent_id {
-            self.backpropagate(id, player);
-        };
+            node_id = node.parent_id;
+        }
+    }
 
+    // backpropagates into the tree and into `table` alike
+    pub fn backpropagate_with_table(
+        &mut self,
+        node_id: usize,
+        player: Option<Player>,
+        table: &mut TranspositionTable<NodeStats>,
+    ) {
+        self.backpropagate(node_id, player);
+
+        let mut node_id = Some(node_id);
+        while let Some(id) = node_id {
+            NodeStats::record(table, &self.nodes[id].game_state, player);
+            node_id = self.nodes[id].parent_id;
+        }
     }
 
     pub fn reccomend(&self) -> usize{
@@ -162,19 +198,152 @@ impl ArenaTree {
         reccomend
     }
 
-    pub fn merge_trees(&self, other: &Self) -> Self {
+    pub fn best_move(&self) -> Option<Move> {
+        self.nodes[0].first_child_id?;
+
+        self.nodes[self.reccomend()].move_from_parent
+    }
+
+    // the most visited line of play from the root
+    pub fn principal_variation(&self) -> Vec<Move> {
+        let mut line = Vec::new();
+        let mut node_id = 0;
+
+        while let (Some(first), Some(last)) = (self.nodes[node_id].first_child_id, self.nodes[node_id].last_child_id) {
+            let best = match (first..last).max_by_key(|&child| self.nodes[child].visits) {
+                Some(best) if self.nodes[best].visits > 0 => best,
+                _ => break,
+            };
+            line.push(self.nodes[best].move_from_parent.unwrap());
+            node_id = best;
+        }
+
+        line
+    }
+
+    // fraction of playouts through this node won by the player who moved into it
+    pub fn win_rate(&self, node_id: usize) -> f32 {
+        let node = &self.nodes[node_id];
+
+        if node.visits == 0 {
+            return 0.5;
+        }
+
+        node.wins as f32 / node.visits as f32
+    }
+
+    // Copies the subtree under node_id into a fresh arena with that node as its root,
+    // keeping all statistics. Children stay contiguous, as `expand_step` expects.
+    pub fn subtree(&self, node_id: usize) -> Self {
         let mut result = ArenaTree{nodes : Vec::new()};
 
-        result.nodes.push(self.nodes[0].merge(&other.nodes[0]));
+        let mut root = self.nodes[node_id];
+        root.parent_id = None;
+        root.move_from_parent = None;
+        result.nodes.push(root);
 
-        let first_child = self.nodes[0].first_child_id.unwrap();
-        let last_child = self.nodes[0].last_child_id.unwrap();
+        // old ids of the nodes already copied, in the same order as result.nodes
+        let mut old_ids = vec![node_id];
+        let mut next = 0;
 
-        for child in first_child..last_child{
-            result.nodes.push(self.nodes[child].merge(&other.nodes[child]));
-        };
+        while next < result.nodes.len() {
+            let node = self.nodes[old_ids[next]];
+
+            if let (Some(first), Some(last)) = (node.first_child_id, node.last_child_id) {
+                let new_first = result.nodes.len();
+
+                for child in first..last {
+                    let mut copy = self.nodes[child];
+                    copy.parent_id = Some(next);
+                    result.nodes.push(copy);
+                    old_ids.push(child);
+                }
+
+                result.nodes[next].first_child_id = Some(new_first);
+                result.nodes[next].last_child_id = Some(result.nodes.len());
+            }
+
+            next += 1;
+        }
+
+        result
+    }
+
+    // Looks for `game` among the positions at most max_depth moves below the root and, if it
+    // is found, makes it the new root so its statistics carry over to the next search.
+    pub fn reroot(&mut self, game: &Game, max_depth: usize) -> bool {
+        let mut frontier = vec![0];
+
+        for depth in 0..=max_depth {
+            if let Some(&found) = frontier.iter().find(|&&id| same_position(&self.nodes[id].game_state, game)) {
+                if found != 0 {
+                    *self = self.subtree(found);
+                }
+                return true;
+            }
+
+            if depth == max_depth {
+                break;
+            }
+
+            frontier = frontier
+                .iter()
+                .filter_map(|&id| Some(self.nodes[id].first_child_id?..self.nodes[id].last_child_id?))
+                .flatten()
+                .collect();
+        }
+
+        false
+    }
+
+    // the child of dst_id playing the same move as other_child, the nth child of its own parent
+    fn matching_child(&self, dst_id: usize, other_child: &Node, nth: usize) -> Option<usize> {
+        let first = self.nodes[dst_id].first_child_id?;
+        let last = self.nodes[dst_id].last_child_id?;
+
+        // both sides usually generated their children in the same order, so try that slot first
+        let same_slot = first + nth;
+        if same_slot < last && self.nodes[same_slot].move_from_parent == other_child.move_from_parent {
+            return Some(same_slot);
+        }
+
+        (first..last).find(|&child| self.nodes[child].move_from_parent == other_child.move_from_parent)
+    }
+
+    // Adds the statistics of the whole of `other` into a copy of this tree, matching nodes by
+    // the moves that lead to them rather than by their arena index. Both trees must have
+    // been grown from the same root position.
+    pub fn merge_trees(&self, other: &Self) -> Self {
+        let mut result = self.subtree(0);
+        let mut stack = vec![(0, 0)];
+
+        while let Some((dst_id, src_id)) = stack.pop() {
+            let src = other.nodes[src_id];
+            result.nodes[dst_id] = result.nodes[dst_id].merge(&src);
+
+            let (Some(src_first), Some(src_last)) = (src.first_child_id, src.last_child_id) else {
+                continue;
+            };
+
+            // only `other` expanded this node, give it an empty copy of the children to fill in
+            if result.nodes[dst_id].first_child_id.is_none() {
+                let new_first = result.nodes.len();
+                for child in src_first..src_last {
+                    let copy = other.nodes[child];
+                    result.new_node(copy.game_state, Some(dst_id), copy.move_from_parent);
+                }
+                result.nodes[dst_id].first_child_id = Some(new_first);
+                result.nodes[dst_id].last_child_id = Some(result.nodes.len());
+            }
+
+            for (nth, child) in (src_first..src_last).enumerate() {
+                if let Some(dst_child) = result.matching_child(dst_id, &other.nodes[child], nth) {
+                    stack.push((dst_child, child));
+                }
+            }
+        }
 
-        return result
+        result
     }
 }
 
@@ -185,12 +354,12 @@ impl Node {
     }
 
     pub fn merge(&self, other: &Self) -> Self {
-        let mut result = self.clone();
+        let mut result = *self;
 
         result.visits += other.visits;
         result.wins += other.wins;
 
-        return result
+        result
     }
 }
 
@@ -0,0 +1,185 @@
+//! Standard Abalone notation for cells and moves.
+//!
+//! Rows are lettered A (the row nearest the first player) to I, and the diagonals are
+//! numbered 1 to 9, so the board runs from A1-A5 up to I5-I9 with E5 in the center.
+//!
+//! An inline move is written as the rearmost marble of the pushing column followed by the
+//! cell it moves into, e.g. `A1B2`. A broadside move is written as the two end marbles of
+//! the line followed by the cell the second one moves into, e.g. `A1C3D3`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::game::{Game, Move, Player, Space};
+use crate::game::DIRS;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseMoveError {
+    InvalidLength(usize),
+    InvalidCell(String),
+    NotAdjacent(String, String),
+    NotInLine(String, String),
+    BroadsideAlongLine,
+}
+
+impl fmt::Display for ParseMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseMoveError::InvalidLength(len) => {
+                write!(f, "expected 2 or 3 cells (4 or 6 characters), got {} characters", len)
+            }
+            ParseMoveError::InvalidCell(cell) => write!(f, "{:?} is not a cell on the board", cell),
+            ParseMoveError::NotAdjacent(a, b) => write!(f, "{} and {} are not adjacent", a, b),
+            ParseMoveError::NotInLine(a, b) => {
+                write!(f, "{} and {} do not bound a line of 2 or 3 marbles", a, b)
+            }
+            ParseMoveError::BroadsideAlongLine => {
+                write!(f, "a broadside move cannot travel along its own line")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseMoveError {}
+
+pub fn on_board(x: i8, y: i8) -> bool {
+    (0..=8).contains(&x) && (0..=8).contains(&y) && (x - y).abs() <= 4
+}
+
+pub fn cell_name(cell: (usize, usize)) -> String {
+    let (x, y) = cell;
+    let row = (b'A' + (y + 4 - x) as u8) as char;
+    format!("{}{}", row, y + 1)
+}
+
+pub fn parse_cell(s: &str) -> Result<(usize, usize), ParseMoveError> {
+    let bytes = s.as_bytes();
+    let invalid = || ParseMoveError::InvalidCell(s.to_string());
+
+    if bytes.len() != 2 {
+        return Err(invalid());
+    }
+
+    let row = bytes[0].to_ascii_uppercase();
+    if !(b'A'..=b'I').contains(&row) || !(b'1'..=b'9').contains(&bytes[1]) {
+        return Err(invalid());
+    }
+
+    let y = (bytes[1] - b'1') as i8;
+    let x = y + 4 - (row - b'A') as i8;
+
+    if !on_board(x, y) {
+        return Err(invalid());
+    }
+
+    Ok((x as usize, y as usize))
+}
+
+// the board drawn in standard orientation with row letters and diagonal numbers
+pub fn board_diagram(game: &Game) -> String {
+    let mut output = String::new();
+
+    for row in (0..9usize).rev() {
+        let indent = row.abs_diff(4);
+        output.push_str(&" ".repeat(indent + 2));
+        output.push((b'A' + row as u8) as char);
+
+        let first = row.saturating_sub(4);
+        let last = (row + 4).min(8);
+        for y in first..=last {
+            let x = y + 4 - row;
+            let marble = match game.space(x, y) {
+                Space::Occupied(Player::White) => 'W',
+                Space::Occupied(Player::Black) => 'B',
+                _ => '.',
+            };
+            output.push(' ');
+            output.push(marble);
+        }
+
+        if row < 4 {
+            output.push_str(&format!(" {}", row + 6));
+        }
+        output.push('\n');
+    }
+    output.push_str("         1 2 3 4 5\n");
+
+    output
+}
+
+fn offset(from: (usize, usize), to: (usize, usize)) -> (i8, i8) {
+    (to.0 as i8 - from.0 as i8, to.1 as i8 - from.1 as i8)
+}
+
+fn step(cell: (usize, usize), dir: (i8, i8)) -> (usize, usize) {
+    ((cell.0 as i8 + dir.0) as usize, (cell.1 as i8 + dir.1) as usize)
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Move::Inline { start, dir } => {
+                write!(f, "{}{}", cell_name(start), cell_name(step(start, dir)))
+            }
+            Move::Broadside { start, stop, dir, .. } => write!(
+                f,
+                "{}{}{}",
+                cell_name(start),
+                cell_name(stop),
+                cell_name(step(stop, dir))
+            ),
+        }
+    }
+}
+
+impl FromStr for Move {
+    type Err = ParseMoveError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if !s.is_ascii() || (s.len() != 4 && s.len() != 6) {
+            return Err(ParseMoveError::InvalidLength(s.chars().count()));
+        }
+
+        let cells = (0..s.len())
+            .step_by(2)
+            .map(|i| parse_cell(&s[i..i + 2]))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let not_adjacent = |a: (usize, usize), b: (usize, usize)| {
+            ParseMoveError::NotAdjacent(cell_name(a), cell_name(b))
+        };
+
+        if cells.len() == 2 {
+            let dir = offset(cells[0], cells[1]);
+            if !DIRS.contains(&dir) {
+                return Err(not_adjacent(cells[0], cells[1]));
+            }
+
+            return Ok(Move::Inline { start: cells[0], dir });
+        }
+
+        let (start, stop, target) = (cells[0], cells[1], cells[2]);
+
+        let dir = offset(stop, target);
+        if !DIRS.contains(&dir) {
+            return Err(not_adjacent(stop, target));
+        }
+
+        let line = offset(start, stop);
+        let mid = if DIRS.contains(&line) {
+            None
+        } else if line.0 % 2 == 0 && line.1 % 2 == 0 && DIRS.contains(&(line.0 / 2, line.1 / 2)) {
+            Some(step(start, (line.0 / 2, line.1 / 2)))
+        } else {
+            return Err(ParseMoveError::NotInLine(cell_name(start), cell_name(stop)));
+        };
+
+        let unit = if mid.is_some() { (line.0 / 2, line.1 / 2) } else { line };
+        if dir == unit || dir == (-unit.0, -unit.1) {
+            return Err(ParseMoveError::BroadsideAlongLine);
+        }
+
+        Ok(Move::Broadside { start, mid, stop, dir })
+    }
+}
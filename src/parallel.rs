@@ -0,0 +1,368 @@
+//! Tree parallel MCTS: every worker thread descends the same tree.
+//!
+//! Nodes live in one arena behind a `RwLock`. Selection, playouts and backpropagation only
+//! need the read lock, with visit and win counters kept in atomics; the write lock is taken
+//! briefly to append a node's children when it is expanded. A thread passing through a node
+//! adds a virtual loss to it until its playout is backed up, steering the other threads
+//! towards different lines while the result is still unknown. A transposition table, if
+//! the policies have one, is locked once to select and once to back up each playout.
+
+use std::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+use std::sync::RwLock;
+use std::thread;
+use std::time::Instant;
+
+use rand::seq::SliceRandom;
+
+use crate::game::{Game, Move, MoveList, Player};
+use crate::node::{ArenaTree, Node};
+use crate::search::{is_decided, Policies, SearchLimits, SearchProgress, DECIDED_CHECK_INTERVAL};
+use crate::selection::{Candidate, SelectionPolicy};
+use crate::transposition::{NodeStats, TranspositionTable};
+
+struct SharedNode {
+    parent_id: Option<usize>,
+    first_child_id: Option<usize>,
+    last_child_id: Option<usize>,
+    visits: AtomicI32,
+    wins: AtomicI32,
+    virtual_loss: AtomicI32,
+    move_from_parent: Option<Move>,
+    game_state: Game,
+}
+
+impl SharedNode {
+    fn children(&self) -> Option<std::ops::Range<usize>> {
+        Some(self.first_child_id?..self.last_child_id?)
+    }
+
+    // visits including the playouts other threads have in flight through this node
+    fn effective_visits(&self) -> i32 {
+        self.visits.load(Ordering::Relaxed) + self.virtual_loss.load(Ordering::Relaxed)
+    }
+}
+
+pub struct SharedTree {
+    nodes: RwLock<Vec<SharedNode>>,
+}
+
+impl SharedTree {
+    pub fn new(root: Game) -> Self {
+        let mut arena = ArenaTree{nodes : Vec::new()};
+        arena.new_node(root, None, None);
+
+        Self::from_arena(arena)
+    }
+
+    pub fn from_arena(arena: ArenaTree) -> Self {
+        let nodes = arena
+            .nodes
+            .into_iter()
+            .map(|node| SharedNode {
+                parent_id: node.parent_id,
+                first_child_id: node.first_child_id,
+                last_child_id: node.last_child_id,
+                visits: AtomicI32::new(node.visits),
+                wins: AtomicI32::new(node.wins),
+                virtual_loss: AtomicI32::new(0),
+                move_from_parent: node.move_from_parent,
+                game_state: node.game_state,
+            })
+            .collect();
+
+        SharedTree { nodes: RwLock::new(nodes) }
+    }
+
+    pub fn into_arena(self) -> ArenaTree {
+        let nodes = self
+            .nodes
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .map(|node| Node {
+                parent_id: node.parent_id,
+                first_child_id: node.first_child_id,
+                last_child_id: node.last_child_id,
+                visits: node.visits.into_inner(),
+                wins: node.wins.into_inner(),
+                move_from_parent: node.move_from_parent,
+                game_state: node.game_state,
+            })
+            .collect();
+
+        ArenaTree { nodes }
+    }
+
+    // a copy of the tree as it stands, for reporting on a search that is still running
+    pub fn snapshot(&self) -> ArenaTree {
+        let nodes = self.nodes.read().unwrap();
+
+        ArenaTree {
+            nodes: nodes
+                .iter()
+                .map(|node| Node {
+                    parent_id: node.parent_id,
+                    first_child_id: node.first_child_id,
+                    last_child_id: node.last_child_id,
+                    visits: node.visits.load(Ordering::Relaxed),
+                    wins: node.wins.load(Ordering::Relaxed),
+                    move_from_parent: node.move_from_parent,
+                    game_state: node.game_state,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // the virtual losses still held anywhere in the tree, none once every playout is backed up
+    pub fn in_flight(&self) -> i32 {
+        self.nodes.read().unwrap().iter().map(|node| node.virtual_loss.load(Ordering::Relaxed)).sum()
+    }
+
+    fn root_visits(&self) -> Vec<i32> {
+        let nodes = self.nodes.read().unwrap();
+
+        nodes[0]
+            .children()
+            .map(|children| children.map(|child| nodes[child].visits.load(Ordering::Relaxed)).collect())
+            .unwrap_or_default()
+    }
+
+    // Walks down from the root by the selection policy, stopping at the first node that still
+    // has children nobody has tried or that has not been expanded. Every node on the way
+    // gets a virtual loss.
+    fn select(
+        &self,
+        policy: &dyn SelectionPolicy,
+        table: Option<&TranspositionTable<NodeStats>>,
+        path: &mut Vec<usize>,
+    ) -> usize {
+        let nodes = self.nodes.read().unwrap();
+        let stats = |node: &SharedNode| {
+            let visits = node.visits.load(Ordering::Relaxed);
+            let stats = NodeStats::lookup(table, &node.game_state, visits, node.wins.load(Ordering::Relaxed));
+            NodeStats { visits: stats.visits + node.virtual_loss.load(Ordering::Relaxed), ..stats }
+        };
+        let mut node_id = 0;
+        let mut candidates = Vec::new();
+
+        loop {
+            path.push(node_id);
+            nodes[node_id].virtual_loss.fetch_add(1, Ordering::Relaxed);
+
+            let Some(children) = nodes[node_id].children() else {
+                return node_id;
+            };
+            if children.clone().any(|child| nodes[child].effective_visits() == 0) {
+                return node_id;
+            }
+
+            // virtual losses count as visits that were not won
+            candidates.clear();
+            candidates.extend(children.clone().map(|child| {
+                let child = &nodes[child];
+                let stats = stats(child);
+                Candidate { visits: stats.visits as f32, wins: stats.wins as f32, game_state: &child.game_state }
+            }));
+
+            node_id = children.start + policy.select(stats(&nodes[node_id]).visits as f32, &candidates);
+        }
+    }
+
+    fn expand(&self, node_id: usize) {
+        let mut nodes = self.nodes.write().unwrap();
+
+        // another thread may have expanded it while this one waited for the lock
+        if nodes[node_id].first_child_id.is_some() {
+            return;
+        }
+
+        let game_state = nodes[node_id].game_state;
+        let first = nodes.len();
+
+        let mut legal_moves = MoveList::new();
+        game_state.generate_moves(&mut legal_moves);
+
+        for &legal_move in legal_moves.iter() {
+            nodes.push(SharedNode {
+                parent_id: Some(node_id),
+                first_child_id: None,
+                last_child_id: None,
+                visits: AtomicI32::new(0),
+                wins: AtomicI32::new(0),
+                virtual_loss: AtomicI32::new(0),
+                move_from_parent: Some(legal_move),
+                game_state: game_state.make_move(&legal_move),
+            });
+        }
+
+        let last = nodes.len();
+        nodes[node_id].first_child_id = Some(first);
+        nodes[node_id].last_child_id = Some(last);
+    }
+
+    // Claims an untried child of node_id with a virtual loss, or any child if other threads
+    // got to all of them first. Returns the game state to play out from.
+    fn claim_child(&self, node_id: usize, path: &mut Vec<usize>) -> Game {
+        let nodes = self.nodes.read().unwrap();
+
+        let Some(children) = nodes[node_id].children() else {
+            return nodes[node_id].game_state;
+        };
+
+        let mut untried: Vec<usize> = children.clone().filter(|&child| nodes[child].effective_visits() == 0).collect();
+        untried.shuffle(&mut rand::thread_rng());
+
+        let claimed = untried
+            .into_iter()
+            .find(|&child| {
+                nodes[child]
+                    .virtual_loss
+                    .compare_exchange(0, 1, Ordering::Relaxed, Ordering::Relaxed)
+                    .is_ok()
+            })
+            .unwrap_or_else(|| {
+                let child = *children.collect::<Vec<_>>().choose(&mut rand::thread_rng()).unwrap();
+                nodes[child].virtual_loss.fetch_add(1, Ordering::Relaxed);
+                child
+            });
+
+        path.push(claimed);
+        nodes[claimed].game_state
+    }
+
+    fn backpropagate(
+        &self,
+        path: &[usize],
+        winner: Option<Player>,
+        mut table: Option<&mut TranspositionTable<NodeStats>>,
+    ) {
+        let nodes = self.nodes.read().unwrap();
+
+        for &node_id in path {
+            let node = &nodes[node_id];
+            if let Some(table) = table.as_deref_mut() {
+                NodeStats::record(table, &node.game_state, winner);
+            }
+
+            node.visits.fetch_add(1, Ordering::Relaxed);
+            if winner.is_some_and(|winner| winner != node.game_state.player) {
+                node.wins.fetch_add(1, Ordering::Relaxed);
+            }
+            node.virtual_loss.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn iteration(&self, policies: &Policies, path: &mut Vec<usize>) {
+        path.clear();
+
+        let table = policies.transpositions.as_deref();
+        let selected = {
+            let table = table.map(|table| table.lock().unwrap());
+            self.select(policies.selection.as_ref(), table.as_deref(), path)
+        };
+        let needs_expansion = {
+            let nodes = self.nodes.read().unwrap();
+            nodes[selected].first_child_id.is_none() && !nodes[selected].game_state.game_over
+        };
+        if needs_expansion {
+            self.expand(selected);
+        }
+
+        let game_state = self.claim_child(selected, path);
+        let winner = if game_state.game_over {
+            game_state.winner
+        } else {
+            policies.playout.playout(&game_state, policies.max_playout_length)
+        };
+
+        self.backpropagate(path, winner, table.map(|table| table.lock().unwrap()).as_deref_mut());
+    }
+}
+
+fn worker<F>(
+    tree: &SharedTree,
+    iterations: &AtomicUsize,
+    limits: &SearchLimits,
+    policies: &Policies,
+    start: Instant,
+    // the size of the tree the search was given, whose nodes are not counted as its own
+    start_len: usize,
+    mut on_progress: F,
+) where
+    F: FnMut(&SharedTree, &SearchProgress),
+{
+    let mut path = Vec::new();
+
+    loop {
+        tree.iteration(policies, &mut path);
+
+        let progress = SearchProgress {
+            iterations: iterations.fetch_add(1, Ordering::Relaxed) + 1,
+            nodes: tree.len() - start_len,
+            elapsed: start.elapsed(),
+        };
+        on_progress(tree, &progress);
+
+        if limits.reached(&progress) {
+            break;
+        }
+
+        if limits.stop_when_decided && progress.iterations.is_multiple_of(DECIDED_CHECK_INTERVAL) {
+            if let Some(remaining) = limits.remaining_iterations(&progress) {
+                if is_decided(tree.root_visits(), remaining) {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+// Keeps searching an existing tree with n_threads workers until one of the limits is reached.
+// `on_progress` is called by one of the workers after each of its iterations. Every worker
+// runs at least one iteration so the root has children.
+pub fn shared_tree_search<F>(
+    arena: ArenaTree,
+    limits: &SearchLimits,
+    policies: &Policies,
+    n_threads: usize,
+    on_progress: F,
+) -> (ArenaTree, SearchProgress)
+where
+    F: FnMut(&SharedTree, &SearchProgress) + Send,
+{
+    let tree = SharedTree::from_arena(arena);
+    let iterations = AtomicUsize::new(0);
+    let start = Instant::now();
+    let start_len = tree.len();
+
+    thread::scope(|scope| {
+        scope.spawn(|| worker(&tree, &iterations, limits, policies, start, start_len, on_progress));
+        for _ in 1..n_threads {
+            scope.spawn(|| worker(&tree, &iterations, limits, policies, start, start_len, |_, _| ()));
+        }
+    });
+
+    let progress = SearchProgress {
+        iterations: iterations.into_inner(),
+        nodes: tree.len() - start_len,
+        elapsed: start.elapsed(),
+    };
+    (tree.into_arena(), progress)
+}
+
+pub fn shared_tree_search_with_limits(
+    root: Game,
+    limits: &SearchLimits,
+    policies: &Policies,
+    n_threads: usize,
+) -> ArenaTree {
+    shared_tree_search(SharedTree::new(root).into_arena(), limits, policies, n_threads, |_, _| ()).0
+}
@@ -0,0 +1,38 @@
+//! Perft: counting the leaves of the tree of legal moves to a fixed depth, to check move
+//! generation and `make_move` against known numbers. `divide` breaks a count down by the
+//! first move, to narrow a wrong count down to the line that goes wrong.
+
+use crate::game::{Game, Move, MoveList};
+
+// The number of sequences of `depth` legal moves from `game`. A game that ends before then
+// has no moves left to count.
+pub fn perft(game: &Game, depth: usize) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    if game.game_over {
+        return 0;
+    }
+
+    let mut moves = MoveList::new();
+    game.generate_moves(&mut moves);
+
+    // the positions one move on are all leaves, so there is no need to make the moves
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    moves.iter().map(|next_move| perft(&game.make_move(next_move), depth - 1)).sum()
+}
+
+// perft of the position after each legal move, for a depth of at least 1
+pub fn divide(game: &Game, depth: usize) -> Vec<(Move, u64)> {
+    if game.game_over {
+        return Vec::new();
+    }
+
+    game.get_legal_moves()
+        .into_iter()
+        .map(|next_move| (next_move, perft(&game.make_move(&next_move), depth.saturating_sub(1))))
+        .collect()
+}
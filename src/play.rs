@@ -0,0 +1,194 @@
+//! Interactive human-versus-engine play over a text terminal.
+//!
+//! The human enters moves in standard notation (`A1B2`, `A1C3D3`) or one of the commands
+//! listed by `help`. Every engine reply is reported together with the engine's estimate of
+//! its winning chances. With a clock, the human's time runs from the engine's reply until a
+//! legal move is entered.
+
+use std::io::{self, BufRead, Write};
+use std::time::Instant;
+
+use crate::clock::{Clock, TimeControl, TimeManager};
+use crate::game::{Game, Move, Player};
+use crate::node::ArenaTree;
+use crate::notation::board_diagram;
+use crate::record::{GameRecord, GameResult};
+use crate::search::{Algorithm, SearchLimits};
+
+const HELP: &str = "\
+commands:
+  <move>   play a move in notation, e.g. A1B2 or A1C3D3
+  moves    list the legal moves
+  hint     ask the engine for a suggestion
+  undo     take back your last move and the engine's reply
+  board    show the board again
+  resign   give up the game
+  quit     leave without finishing the game
+";
+
+pub struct PlaySettings {
+    pub human: Player,
+    // how long the engine thinks when there is no clock, and for hints
+    pub limits: SearchLimits,
+    pub clock: Option<TimeControl>,
+    pub algorithm: Algorithm,
+    pub threads: usize,
+}
+
+// the engine's choice and its estimated chance of winning after playing it, searching on
+// from the tree kept in `tree`
+fn search(game: &Game, limits: &SearchLimits, settings: &PlaySettings, tree: &mut Option<ArenaTree>) -> (Move, f32) {
+    let outcome = settings.algorithm.search_with_tree(*game, limits, settings.threads, tree);
+
+    (outcome.best_move, outcome.win_rate)
+}
+
+fn show<W: Write>(output: &mut W, game: &Game, clock: &Option<Clock>) -> io::Result<()> {
+    writeln!(output, "{}", board_diagram(game))?;
+    writeln!(
+        output,
+        "move {}, {:?} to play, white has lost {}, black has lost {}",
+        game.move_number + 1,
+        game.player,
+        14 - game.white_pieces,
+        14 - game.black_pieces
+    )?;
+    if let Some(clock) = clock {
+        writeln!(output, "clock: {}", clock)?;
+    }
+    Ok(())
+}
+
+fn lost_on_time(player: Player) -> GameResult {
+    match player {
+        Player::White => GameResult::BlackWins,
+        Player::Black => GameResult::WhiteWins,
+    }
+}
+
+pub fn play<R: BufRead, W: Write>(
+    start: Game,
+    settings: &PlaySettings,
+    mut input: R,
+    mut output: W,
+) -> io::Result<GameRecord> {
+    let mut record = GameRecord::new(start);
+    let mut game = start;
+    let mut clock = settings.clock.map(Clock::new);
+    // the clock as it stood before each recorded move, to set it back on undo
+    let mut clock_history: Vec<Option<Clock>> = Vec::new();
+    let time_manager = TimeManager::default();
+    let mut turn_started = Instant::now();
+    let mut tree = None;
+
+    writeln!(output, "you are playing {:?}, type help for a list of commands", settings.human)?;
+    show(&mut output, &game, &clock)?;
+
+    while !game.game_over {
+        if game.player != settings.human {
+            let limits = match &clock {
+                Some(clock) => SearchLimits::time(time_manager.allocate(clock, &game)),
+                None => settings.limits.clone(),
+            };
+
+            let started = Instant::now();
+            let (engine_move, win_rate) = search(&game, &limits, settings, &mut tree);
+            clock_history.push(clock.clone());
+            if let Some(clock) = &mut clock {
+                if !clock.punch(game.player, started.elapsed()) {
+                    writeln!(output, "the engine lost on time")?;
+                    record.result = lost_on_time(game.player);
+                    return Ok(record);
+                }
+            }
+            game = record.push(engine_move).unwrap();
+
+            writeln!(output, "engine plays {} (expects to win {:.0}%)", engine_move, win_rate * 100.0)?;
+            show(&mut output, &game, &clock)?;
+            turn_started = Instant::now();
+            continue;
+        }
+
+        write!(output, "> ")?;
+        output.flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(record);
+        }
+
+        match line.trim().to_ascii_lowercase().as_str() {
+            "" => (),
+            "help" => write!(output, "{}", HELP)?,
+            "board" => show(&mut output, &game, &clock)?,
+            "moves" => {
+                let moves: Vec<String> = game.get_legal_moves().iter().map(Move::to_string).collect();
+                writeln!(output, "{}", moves.join(" "))?;
+            }
+            "hint" => {
+                let (hint, win_rate) = search(&game, &settings.limits, settings, &mut tree);
+                writeln!(output, "engine suggests {} (expects to win {:.0}%)", hint, win_rate * 100.0)?;
+            }
+            "undo" => {
+                // take back moves until it is the human's turn again, if the human has moved at all
+                let mut positions = record.replay();
+                let human_moves = positions[..positions.len() - 1]
+                    .iter()
+                    .filter(|position| position.player == settings.human)
+                    .count();
+
+                if human_moves == 0 {
+                    writeln!(output, "nothing to undo")?;
+                    continue;
+                }
+
+                loop {
+                    record.pop();
+                    positions.pop();
+                    clock = clock_history.pop().unwrap();
+                    if positions.last().unwrap().player == settings.human {
+                        break;
+                    }
+                }
+                game = *positions.last().unwrap();
+                show(&mut output, &game, &clock)?;
+                turn_started = Instant::now();
+            }
+            "resign" => {
+                record.result = match settings.human {
+                    Player::White => GameResult::BlackWins,
+                    Player::Black => GameResult::WhiteWins,
+                };
+                writeln!(output, "{:?} resigns", settings.human)?;
+                return Ok(record);
+            }
+            "quit" => return Ok(record),
+            text => match text.parse::<Move>() {
+                Ok(human_move) => match record.push(human_move) {
+                    Ok(next_state) => {
+                        clock_history.push(clock.clone());
+                        if let Some(clock) = &mut clock {
+                            if !clock.punch(settings.human, turn_started.elapsed()) {
+                                record.pop();
+                                writeln!(output, "you lost on time")?;
+                                record.result = lost_on_time(settings.human);
+                                return Ok(record);
+                            }
+                        }
+                        game = next_state;
+                    }
+                    Err(_) => writeln!(output, "{} is not a legal move", human_move)?,
+                },
+                Err(error) => writeln!(output, "{}, type help for a list of commands", error)?,
+            },
+        }
+    }
+
+    match game.winner {
+        Some(winner) if winner == settings.human => writeln!(output, "you win")?,
+        Some(_) => writeln!(output, "{:?} wins", settings.human.opponent())?,
+        None => writeln!(output, "the game is drawn")?,
+    }
+
+    Ok(record)
+}
@@ -0,0 +1,274 @@
+//! Policies for the playout step of MCTS, playing a game out from a newly expanded node to
+//! find a result to back up.
+//!
+//! Most policies only decide which move to make next and leave the playing out to the
+//! default `PlayoutPolicy::playout`. A playout cut off before the game ends is called for
+//! the side that has lost fewer marbles, and when they have lost the same number for the
+//! side the static evaluation favours. `DepthLimited` goes by its evaluation alone.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::eval::{center_distance, cohesion, Weights};
+use crate::game::{Game, Move, MoveList, Player, MAX_MOVES};
+
+pub trait PlayoutPolicy: Send + Sync {
+    // the move to make in `game`, one of `moves`, which is never empty
+    fn choose_move(&self, game: &Game, moves: &[Move]) -> Move;
+
+    // plays `game` to the end, or for at most `max_length` moves if given, returning the
+    // winner if there is one
+    fn playout(&self, game: &Game, max_length: Option<usize>) -> Option<Player> {
+        play_out(self, game, max_length)
+    }
+
+    // who a playout cut off at `game` counts as won for
+    fn truncated_result(&self, game: &Game) -> Option<Player> {
+        match game.white_pieces.cmp(&game.black_pieces) {
+            std::cmp::Ordering::Greater => Some(Player::White),
+            std::cmp::Ordering::Less => Some(Player::Black),
+            std::cmp::Ordering::Equal => evaluated_result(&Weights::default(), game),
+        }
+    }
+}
+
+// the side `weights` scores `game` in favour of, if either
+fn evaluated_result(weights: &Weights, game: &Game) -> Option<Player> {
+    match weights.evaluate_for(game, Player::White).signum() {
+        1 => Some(Player::White),
+        -1 => Some(Player::Black),
+        _ => None,
+    }
+}
+
+fn play_out<P: PlayoutPolicy + ?Sized>(policy: &P, game: &Game, max_length: Option<usize>) -> Option<Player> {
+    let mut game = *game;
+    let mut length = 0;
+    let mut moves = MoveList::new();
+
+    while !game.game_over {
+        if max_length.is_some_and(|max_length| length >= max_length) {
+            return policy.truncated_result(&game);
+        }
+
+        game.generate_moves(&mut moves);
+        game = game.make_move(&policy.choose_move(&game, &moves));
+        length += 1;
+    }
+
+    game.winner
+}
+
+fn is_capture(game: &Game, next_state: &Game) -> bool {
+    next_state.white_pieces < game.white_pieces || next_state.black_pieces < game.black_pieces
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandomPlayout;
+
+impl PlayoutPolicy for RandomPlayout {
+    fn choose_move(&self, _game: &Game, moves: &[Move]) -> Move {
+        *moves.choose(&mut rand::thread_rng()).unwrap()
+    }
+}
+
+// Pushes a marble off whenever it can, otherwise plays at random
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GreedyPlayout;
+
+impl PlayoutPolicy for GreedyPlayout {
+    fn choose_move(&self, game: &Game, moves: &[Move]) -> Move {
+        moves
+            .iter()
+            .find(|candidate| is_capture(game, &game.make_move(candidate)))
+            .copied()
+            .unwrap_or_else(|| *moves.choose(&mut rand::thread_rng()).unwrap())
+    }
+}
+
+// Scores each move by the position it leads to and samples moves with probability growing
+// exponentially in their score, so good moves are likely but never certain.
+#[derive(Debug, Clone, Copy)]
+pub struct HeuristicPlayout {
+    pub capture: f32,
+    pub center: f32,
+    pub cohesion: f32,
+    pub temperature: f32,
+}
+
+impl Default for HeuristicPlayout {
+    fn default() -> Self {
+        HeuristicPlayout { capture: 4.0, center: 0.3, cohesion: 0.2, temperature: 1.0 }
+    }
+}
+
+impl HeuristicPlayout {
+    // how good `next_state` is for the player who moved into it, from `game`
+    fn score(&self, game: &Game, next_state: &Game) -> f32 {
+        let mover = game.player;
+        let captured = i32::from(is_capture(game, next_state)) as f32;
+        let center = (center_distance(game, mover) - center_distance(next_state, mover)) as f32;
+        let cohesion = (cohesion(next_state, mover) - cohesion(game, mover)) as f32;
+
+        self.capture * captured + self.center * center + self.cohesion * cohesion
+    }
+
+    fn best_move(&self, game: &Game, moves: &[Move]) -> Move {
+        let mut best = moves[0];
+        let mut best_score = f32::MIN;
+
+        for candidate in moves {
+            let score = self.score(game, &game.make_move(candidate));
+            if score > best_score {
+                best = *candidate;
+                best_score = score;
+            }
+        }
+
+        best
+    }
+}
+
+impl PlayoutPolicy for HeuristicPlayout {
+    fn choose_move(&self, game: &Game, moves: &[Move]) -> Move {
+        let mut weights = [0.0; MAX_MOVES];
+        let weights = &mut weights[..moves.len()];
+        for (weight, candidate) in weights.iter_mut().zip(moves) {
+            *weight = self.score(game, &game.make_move(candidate));
+        }
+
+        // shifting by the best score keeps the exponentials from overflowing
+        let max = weights.iter().copied().fold(f32::MIN, f32::max);
+        for weight in weights.iter_mut() {
+            *weight = ((*weight - max) / self.temperature).exp();
+        }
+
+        let mut pick = rand::thread_rng().gen::<f32>() * weights.iter().sum::<f32>();
+        for (candidate, weight) in moves.iter().zip(weights.iter()) {
+            if pick < *weight {
+                return *candidate;
+            }
+            pick -= weight;
+        }
+
+        *moves.last().unwrap()
+    }
+}
+
+// Plays the heuristic's best move, or a random move with probability `epsilon`
+#[derive(Debug, Clone, Copy)]
+pub struct EpsilonGreedyPlayout {
+    pub epsilon: f32,
+    pub heuristic: HeuristicPlayout,
+}
+
+impl Default for EpsilonGreedyPlayout {
+    fn default() -> Self {
+        EpsilonGreedyPlayout { epsilon: 0.1, heuristic: HeuristicPlayout::default() }
+    }
+}
+
+impl PlayoutPolicy for EpsilonGreedyPlayout {
+    fn choose_move(&self, game: &Game, moves: &[Move]) -> Move {
+        let mut rng = rand::thread_rng();
+
+        if rng.gen::<f32>() < self.epsilon {
+            *moves.choose(&mut rng).unwrap()
+        } else {
+            self.heuristic.best_move(game, moves)
+        }
+    }
+}
+
+// Plays at most `depth` moves with another policy, whatever length the search allows, and
+// calls a playout that is cut off for the side `weights` scores as better off
+#[derive(Clone)]
+pub struct DepthLimited {
+    pub depth: usize,
+    pub policy: Arc<dyn PlayoutPolicy>,
+    pub weights: Weights,
+}
+
+impl Default for DepthLimited {
+    fn default() -> Self {
+        DepthLimited { depth: 20, policy: Arc::new(GreedyPlayout), weights: Weights::default() }
+    }
+}
+
+impl fmt::Debug for DepthLimited {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DepthLimited").field("depth", &self.depth).finish_non_exhaustive()
+    }
+}
+
+impl PlayoutPolicy for DepthLimited {
+    fn choose_move(&self, game: &Game, moves: &[Move]) -> Move {
+        self.policy.choose_move(game, moves)
+    }
+
+    fn playout(&self, game: &Game, max_length: Option<usize>) -> Option<Player> {
+        let depth = max_length.map_or(self.depth, |max_length| max_length.min(self.depth));
+        play_out(self, game, Some(depth))
+    }
+
+    fn truncated_result(&self, game: &Game) -> Option<Player> {
+        evaluated_result(&self.weights, game)
+    }
+}
+
+// The playout policies above that can be asked for by name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayoutKind {
+    Random,
+    Greedy,
+    Heuristic,
+    EpsilonGreedy,
+    DepthLimited,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsePlayoutError(String);
+
+impl fmt::Display for ParsePlayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "unknown playout policy {:?}, expected random, greedy, heuristic, epsilon-greedy or depth-limited",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParsePlayoutError {}
+
+impl FromStr for PlayoutKind {
+    type Err = ParsePlayoutError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "random" => Ok(PlayoutKind::Random),
+            "greedy" => Ok(PlayoutKind::Greedy),
+            "heuristic" => Ok(PlayoutKind::Heuristic),
+            "epsilon-greedy" => Ok(PlayoutKind::EpsilonGreedy),
+            "depth-limited" => Ok(PlayoutKind::DepthLimited),
+            _ => Err(ParsePlayoutError(s.to_string())),
+        }
+    }
+}
+
+impl PlayoutKind {
+    // the policy with its default settings
+    pub fn policy(self) -> Arc<dyn PlayoutPolicy> {
+        match self {
+            PlayoutKind::Random => Arc::new(RandomPlayout),
+            PlayoutKind::Greedy => Arc::new(GreedyPlayout),
+            PlayoutKind::Heuristic => Arc::new(HeuristicPlayout::default()),
+            PlayoutKind::EpsilonGreedy => Arc::new(EpsilonGreedyPlayout::default()),
+            PlayoutKind::DepthLimited => Arc::new(DepthLimited::default()),
+        }
+    }
+}
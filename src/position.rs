@@ -0,0 +1,211 @@
+//! A compact, FEN-like text encoding of a `Game`.
+//!
+//! A position is written as five space separated fields:
+//!
+//! `<board> <player> <white score> <black score> <move number>`
+//!
+//! The board lists rows from I down to A, separated by `/`. Within a row cells run from the
+//! lowest numbered diagonal to the highest, with `W` and `B` for marbles and a digit for a
+//! run of empty cells. The player to move is `w` or `b`, and the scores are the number of
+//! opposing marbles each player has pushed off the board. The standard opening is
+//!
+//! `BBBBB/BBBBBB/2BBB2/8/9/8/2WWW2/WWWWWW/WWWWW w 0 0 0`
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::game::{Game, Player, Space};
+
+pub const STARTING_PIECES: i16 = 14;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParsePositionError {
+    WrongFieldCount(usize),
+    WrongRowCount(usize),
+    InvalidRowLength { row: char, expected: usize, found: usize },
+    InvalidCharacter(char),
+    InvalidPlayer(String),
+    InvalidNumber(String),
+    ScoreMismatch { player: Player, score: i16, on_board: i16 },
+}
+
+impl fmt::Display for ParsePositionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParsePositionError::WrongFieldCount(n) => write!(f, "expected 5 fields, found {}", n),
+            ParsePositionError::WrongRowCount(n) => write!(f, "expected 9 rows, found {}", n),
+            ParsePositionError::InvalidRowLength { row, expected, found } => {
+                write!(f, "row {} should have {} cells, found {}", row, expected, found)
+            }
+            ParsePositionError::InvalidCharacter(c) => write!(f, "unexpected character {:?} in board", c),
+            ParsePositionError::InvalidPlayer(s) => write!(f, "player to move must be w or b, found {:?}", s),
+            ParsePositionError::InvalidNumber(s) => write!(f, "{:?} is not a valid count", s),
+            ParsePositionError::ScoreMismatch { player, score, on_board } => write!(
+                f,
+                "{:?} has {} marbles on the board, which does not match the opponent's score of {}",
+                player, on_board, score
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParsePositionError {}
+
+const ROW_NAMES: [char; 9] = ['A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I'];
+
+// cells of row r (0 = A), ordered by diagonal
+fn row_cells(row: usize) -> impl Iterator<Item = (usize, usize)> {
+    let first = row.saturating_sub(4);
+    let last = (row + 4).min(8);
+    (first..=last).map(move |y| (y + 4 - row, y))
+}
+
+fn parse_count(s: &str) -> Result<i16, ParsePositionError> {
+    match s.parse::<i16>() {
+        Ok(n) if n >= 0 => Ok(n),
+        _ => Err(ParsePositionError::InvalidNumber(s.to_string())),
+    }
+}
+
+impl Game {
+    pub fn to_position(&self) -> String {
+        let mut rows: Vec<String> = Vec::new();
+
+        for row in (0..9).rev() {
+            let mut encoded = String::new();
+            let mut empty = 0;
+
+            for (x, y) in row_cells(row) {
+                let marble = match self.space(x, y) {
+                    Space::Occupied(Player::White) => 'W',
+                    Space::Occupied(Player::Black) => 'B',
+                    _ => {
+                        empty += 1;
+                        continue;
+                    }
+                };
+                if empty > 0 {
+                    encoded.push_str(&empty.to_string());
+                    empty = 0;
+                }
+                encoded.push(marble);
+            }
+            if empty > 0 {
+                encoded.push_str(&empty.to_string());
+            }
+            rows.push(encoded);
+        }
+
+        let player = match self.player {
+            Player::White => 'w',
+            Player::Black => 'b',
+        };
+
+        format!(
+            "{} {} {} {} {}",
+            rows.join("/"),
+            player,
+            STARTING_PIECES - self.black_pieces,
+            STARTING_PIECES - self.white_pieces,
+            self.move_number
+        )
+    }
+
+    pub fn from_position(s: &str) -> Result<Self, ParsePositionError> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(ParsePositionError::WrongFieldCount(fields.len()));
+        }
+
+        let rows: Vec<&str> = fields[0].split('/').collect();
+        if rows.len() != 9 {
+            return Err(ParsePositionError::WrongRowCount(rows.len()));
+        }
+
+        let mut game = Game::new_game();
+
+        for (row, encoded) in (0..9).rev().zip(rows) {
+            let cells: Vec<(usize, usize)> = row_cells(row).collect();
+            let mut i = 0;
+
+            for c in encoded.chars() {
+                let space = match c {
+                    'W' | 'w' => Space::Occupied(Player::White),
+                    'B' | 'b' => Space::Occupied(Player::Black),
+                    '1'..='9' => {
+                        i += c.to_digit(10).unwrap() as usize;
+                        continue;
+                    }
+                    _ => return Err(ParsePositionError::InvalidCharacter(c)),
+                };
+                if let Some(&(x, y)) = cells.get(i) {
+                    game.set(x, y, space);
+                }
+                i += 1;
+            }
+
+            if i != cells.len() {
+                return Err(ParsePositionError::InvalidRowLength {
+                    row: ROW_NAMES[row],
+                    expected: cells.len(),
+                    found: i,
+                });
+            }
+        }
+
+        game.player = match fields[1] {
+            "w" | "W" => Player::White,
+            "b" | "B" => Player::Black,
+            other => return Err(ParsePositionError::InvalidPlayer(other.to_string())),
+        };
+
+        let white_score = parse_count(fields[2])?;
+        let black_score = parse_count(fields[3])?;
+        game.move_number = parse_count(fields[4])?;
+
+        for row in game.board() {
+            for space in row {
+                match space {
+                    Space::Occupied(Player::White) => game.white_pieces += 1,
+                    Space::Occupied(Player::Black) => game.black_pieces += 1,
+                    _ => (),
+                }
+            }
+        }
+
+        if game.white_pieces + black_score != STARTING_PIECES {
+            return Err(ParsePositionError::ScoreMismatch {
+                player: Player::White,
+                score: black_score,
+                on_board: game.white_pieces,
+            });
+        }
+        if game.black_pieces + white_score != STARTING_PIECES {
+            return Err(ParsePositionError::ScoreMismatch {
+                player: Player::Black,
+                score: white_score,
+                on_board: game.black_pieces,
+            });
+        }
+
+        if game.white_pieces <= 8 {
+            game.winner = Some(Player::Black);
+            game.game_over = true;
+        } else if game.black_pieces <= 8 {
+            game.winner = Some(Player::White);
+            game.game_over = true;
+        } else if game.move_number > 1000 {
+            game.game_over = true;
+        }
+
+        Ok(game)
+    }
+}
+
+impl FromStr for Game {
+    type Err = ParsePositionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Game::from_position(s)
+    }
+}
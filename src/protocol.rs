@@ -0,0 +1,451 @@
+//! The Abalone Engine Protocol (AEP), a line based protocol in the spirit of UCI and GTP
+//! that lets GUIs and tournament managers drive the engine as a subprocess.
+//!
+//! Commands sent to the engine:
+//!
+//! ```text
+//! aep                                   identify the engine, answered by `aepok`
+//! isready                               answered by `readyok` once earlier commands are done
+//! newgame                               reset to the standard layout and forget earlier searches
+//! layout <name>                         reset to a built in starting layout or one from a file,
+//!                                       see the `layout` module
+//! position startpos [moves <m>...]      the standard layout followed by some moves
+//! position layout <name> [moves <m>...] a built in or file layout followed by some moves
+//! position fen <position> [moves <m>...] a position in the `position` module's format
+//! play <m>...                           play moves from the current position
+//! go [iterations <n>] [nodes <n>] [movetime <ms>] [earlystop] [infinite]
+//!    [wtime <ms>] [btime <ms>] [winc <ms>] [binc <ms>] [movestogo <n>]
+//! stop                                  finish the current search early
+//! engine <mcts|alphabeta>               the search algorithm, mcts by default
+//! threads <n>                           search with n threads from the next go on, 1 by default
+//! selection <policy> [<exploration>]    ucb1 (the default), ucb1-tuned, puct or thompson,
+//!                                       where puct is guided by a capture and centre prior
+//! playout <policy> [<length>]           random, greedy (the default), heuristic, epsilon-greedy or
+//!                                       depth-limited, which stops after 20 moves and scores the
+//!                                       position; cut off after length moves if given
+//! hash <entries>                        share MCTS statistics between transpositions in a
+//!                                       table of this many entries, 0 (the default) for none
+//! weights <file>                        alpha-beta evaluation weights, see the `eval` module
+//! show                                  print the board and its position string
+//! legal                                 list the legal moves
+//! quit
+//! ```
+//!
+//! With `wtime`/`btime` the engine budgets its own time from the clock of the side to move.
+//! Statistics from the previous search are reused when the new position follows from it, and
+//! alpha-beta keeps its transposition table from one search to the next.
+//! `earlystop` ends a search as soon as its best move can no longer change within its limits.
+//! An MCTS search prints `info` lines about once a second, alpha-beta prints one for every
+//! completed depth, where an iteration is a depth. Either finishes with
+//! `bestmove <move> pv <move>...`, or `bestmove none` when the game is over.
+//! Any other command stops a running search first. Malformed commands are answered
+//! with a single `error <message>` line.
+
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::alphabeta::{alphabeta_search_with_table, AlphaBetaResult, TableEntry, DEFAULT_TABLE_ENTRIES};
+use crate::clock::TimeManager;
+use crate::eval::Weights;
+use crate::game::{Game, Move, Player};
+use crate::layout::Layout;
+use crate::node::ArenaTree;
+use crate::notation::board_diagram;
+use crate::parallel::shared_tree_search;
+use crate::search::{reuse_tree, Policies, SearchLimits, SearchProgress};
+use crate::playout::PlayoutKind;
+use crate::selection::SelectionKind;
+use crate::transposition::TranspositionTable;
+
+const DEFAULT_ITERATIONS: usize = 10000;
+const INFO_INTERVAL: Duration = Duration::from_secs(1);
+
+fn parse_go(args: &[&str], game: &Game) -> Result<SearchLimits, String> {
+    let mut limits = SearchLimits::default();
+    let mut infinite = false;
+    let mut clock: [Option<u64>; 4] = [None; 4];
+    let mut moves_to_go: Option<u32> = None;
+    let mut args = args.iter();
+
+    while let Some(&arg) = args.next() {
+        match arg {
+            "infinite" => infinite = true,
+            "earlystop" => limits.stop_when_decided = true,
+            _ => {
+                let value: u64 = args
+                    .next()
+                    .and_then(|value| value.parse().ok())
+                    .ok_or_else(|| format!("{} needs a number", arg))?;
+
+                match arg {
+                    "iterations" => limits.iterations = Some(value as usize),
+                    "nodes" => limits.nodes = Some(value as usize),
+                    "movetime" => limits.time = Some(Duration::from_millis(value)),
+                    "wtime" => clock[0] = Some(value),
+                    "btime" => clock[1] = Some(value),
+                    "winc" => clock[2] = Some(value),
+                    "binc" => clock[3] = Some(value),
+                    "movestogo" => moves_to_go = Some(value as u32),
+                    _ => return Err(format!("unknown go option {}", arg)),
+                }
+            }
+        }
+    }
+
+    let (remaining, increment) = match game.player {
+        Player::White => (clock[0], clock[2]),
+        Player::Black => (clock[1], clock[3]),
+    };
+    if let Some(remaining) = remaining {
+        let budget = TimeManager::default().allocate_from(
+            game,
+            Duration::from_millis(remaining),
+            Duration::from_millis(increment.unwrap_or(0)),
+            moves_to_go,
+        );
+        limits.time = Some(limits.time.map_or(budget, |time| time.min(budget)));
+    }
+
+    if infinite {
+        limits.iterations = None;
+        limits.nodes = None;
+        limits.time = None;
+    } else if limits.iterations.is_none() && limits.nodes.is_none() && limits.time.is_none() {
+        limits.iterations = Some(DEFAULT_ITERATIONS);
+    }
+
+    Ok(limits)
+}
+
+struct RunningSearch {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<Option<ArenaTree>>,
+}
+
+struct Engine<W: Write + Send + 'static> {
+    game: Game,
+    output: Arc<Mutex<W>>,
+    search: Option<RunningSearch>,
+    // the tree from the last search, kept so the next one can start from its statistics
+    tree: Option<ArenaTree>,
+    alphabeta: bool,
+    // the alpha-beta table, kept between searches so that each starts from what the last found
+    alphabeta_table: Arc<Mutex<TranspositionTable<TableEntry>>>,
+    weights: Weights,
+    policies: Policies,
+    threads: usize,
+}
+
+fn send<W: Write>(output: &Mutex<W>, line: &str) {
+    let mut output = output.lock().unwrap();
+    // the GUI going away mid-search is not something the engine can do anything about
+    let _ = writeln!(output, "{}", line).and_then(|_| output.flush());
+}
+
+fn moves_to_string(moves: &[Move]) -> String {
+    moves.iter().map(Move::to_string).collect::<Vec<_>>().join(" ")
+}
+
+fn info(arena: &ArenaTree, progress: &SearchProgress) -> String {
+    let mut line = format!(
+        "info iterations {} nodes {} time {}",
+        progress.iterations,
+        progress.nodes,
+        progress.elapsed.as_millis()
+    );
+
+    if arena.nodes[0].first_child_id.is_some() {
+        line.push_str(&format!(
+            " winrate {:.3} pv {}",
+            arena.win_rate(arena.reccomend()),
+            moves_to_string(&arena.principal_variation())
+        ));
+    }
+
+    line
+}
+
+fn alphabeta_info(result: &AlphaBetaResult) -> String {
+    format!(
+        "info iterations {} nodes {} time {} score {} winrate {:.3} pv {}",
+        result.depth,
+        result.progress.nodes,
+        result.progress.elapsed.as_millis(),
+        result.score,
+        result.win_rate(),
+        moves_to_string(&result.principal_variation)
+    )
+}
+
+fn alphabeta<W: Write>(
+    game: Game,
+    limits: SearchLimits,
+    weights: &Weights,
+    table: &Mutex<TranspositionTable<TableEntry>>,
+    output: &Mutex<W>,
+) {
+    if game.game_over {
+        send(output, "bestmove none");
+        return;
+    }
+
+    let mut table = table.lock().unwrap();
+    let result = alphabeta_search_with_table(game, &limits, weights, &mut table, |result| {
+        send(output, &alphabeta_info(result));
+    });
+    send(
+        output,
+        &format!("bestmove {} pv {}", result.best_move.unwrap(), moves_to_string(&result.principal_variation)),
+    );
+}
+
+fn search<W: Write + Send>(
+    game: Game,
+    tree: Option<ArenaTree>,
+    limits: SearchLimits,
+    policies: Policies,
+    threads: usize,
+    output: &Mutex<W>,
+) -> Option<ArenaTree> {
+    if game.game_over {
+        send(output, "bestmove none");
+        return tree;
+    }
+
+    let arena = reuse_tree(tree, game);
+    let mut last_info = Duration::ZERO;
+    let (arena, progress) = shared_tree_search(arena, &limits, &policies, threads, |tree, progress| {
+        if progress.elapsed - last_info >= INFO_INTERVAL {
+            send(output, &info(&tree.snapshot(), progress));
+            last_info = progress.elapsed;
+        }
+    });
+
+    send(output, &info(&arena, &progress));
+    send(
+        output,
+        &format!("bestmove {} pv {}", arena.best_move().unwrap(), moves_to_string(&arena.principal_variation())),
+    );
+
+    Some(arena)
+}
+
+impl<W: Write + Send + 'static> Engine<W> {
+    fn stop_search(&mut self) {
+        if let Some(running) = self.search.take() {
+            running.stop.store(true, Ordering::Relaxed);
+            self.tree = running.handle.join().unwrap();
+        }
+    }
+
+    fn play_moves(&mut self, game: Game, moves: &[&str]) -> Result<(), String> {
+        let mut game = game;
+
+        for text in moves {
+            let next_move: Move = text.parse().map_err(|error| format!("{}: {}", text, error))?;
+            if game.game_over || !game.get_legal_moves().contains(&next_move) {
+                return Err(format!("{} is not a legal move", next_move));
+            }
+            game = game.make_move(&next_move);
+        }
+
+        self.game = game;
+        Ok(())
+    }
+
+    fn set_position(&mut self, args: &[&str]) -> Result<(), String> {
+        let moves_at = args.iter().position(|&arg| arg == "moves").unwrap_or(args.len());
+        let (description, moves) = args.split_at(moves_at);
+        let moves = moves.get(1..).unwrap_or(&[]);
+
+        let game = match description {
+            ["startpos"] => Game::new_basic(),
+            ["layout", name] => Layout::find(name).map_err(|error| error.to_string())?.game(),
+            ["fen", fields @ ..] => Game::from_position(&fields.join(" ")).map_err(|error| error.to_string())?,
+            _ => return Err("expected startpos, layout <name> or fen <position>".to_string()),
+        };
+
+        self.play_moves(game, moves)
+    }
+
+    fn set_selection(&mut self, args: &[&str]) -> Result<(), String> {
+        let (kind, exploration) = match args {
+            [kind] => (kind, None),
+            [kind, exploration] => {
+                let exploration = exploration.parse().map_err(|_| format!("{} is not a number", exploration))?;
+                (kind, Some(exploration))
+            }
+            _ => return Err("usage: selection <policy> [<exploration>]".to_string()),
+        };
+        let kind = kind.parse::<SelectionKind>().map_err(|error| error.to_string())?;
+
+        // statistics gathered under another policy are still good visit counts, so the tree stays
+        self.policies.selection = kind.policy(exploration);
+        Ok(())
+    }
+
+    fn set_playout(&mut self, args: &[&str]) -> Result<(), String> {
+        let (kind, length) = match args {
+            [kind] => (kind, None),
+            [kind, length] => (kind, Some(length.parse().map_err(|_| format!("{} is not a length", length))?)),
+            _ => return Err("usage: playout <policy> [<length>]".to_string()),
+        };
+        let kind = kind.parse::<PlayoutKind>().map_err(|error| error.to_string())?;
+
+        self.policies.playout = kind.policy();
+        self.policies.max_playout_length = length;
+        Ok(())
+    }
+
+    fn go(&mut self, args: &[&str]) -> Result<(), String> {
+        let mut limits = parse_go(args, &self.game)?;
+        let stop = Arc::new(AtomicBool::new(false));
+        limits.stop = Some(Arc::clone(&stop));
+
+        let game = self.game;
+        if self.alphabeta {
+            let weights = self.weights;
+            let table = Arc::clone(&self.alphabeta_table);
+            let output = Arc::clone(&self.output);
+            let handle = thread::spawn(move || {
+                alphabeta(game, limits, &weights, &table, &output);
+                None
+            });
+
+            self.search = Some(RunningSearch { stop, handle });
+            return Ok(());
+        }
+
+        let tree = self.tree.take();
+        let policies = self.policies.clone();
+        let threads = self.threads;
+        let output = Arc::clone(&self.output);
+        let handle = thread::spawn(move || search(game, tree, limits, policies, threads, &output));
+
+        self.search = Some(RunningSearch { stop, handle });
+        Ok(())
+    }
+
+    // returns false once the engine should exit
+    fn handle(&mut self, line: &str) -> bool {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((&command, args)) = words.split_first() else {
+            return true;
+        };
+
+        if command != "isready" {
+            self.stop_search();
+        }
+
+        let result = match command {
+            "aep" => {
+                send(&self.output, &format!("id name abalone {}", env!("CARGO_PKG_VERSION")));
+                send(&self.output, "aepok");
+                Ok(())
+            }
+            "isready" => {
+                send(&self.output, "readyok");
+                Ok(())
+            }
+            "newgame" => {
+                self.game = Game::new_basic();
+                self.tree = None;
+                self.alphabeta_table.lock().unwrap().clear();
+                self.policies = self.policies.with_fresh_transpositions();
+                Ok(())
+            }
+            "layout" => match args {
+                [name] => Layout::find(name)
+                    .map(|layout| self.game = layout.game())
+                    .map_err(|error| error.to_string()),
+                _ => Err("usage: layout <name>".to_string()),
+            },
+            "position" => self.set_position(args),
+            "play" => self.play_moves(self.game, args),
+            "go" => self.go(args),
+            "stop" => Ok(()),
+            "threads" => match args {
+                [threads] => match threads.parse() {
+                    Ok(threads) if threads > 0 => {
+                        self.threads = threads;
+                        Ok(())
+                    }
+                    _ => Err(format!("{} is not a thread count", threads)),
+                },
+                _ => Err("usage: threads <n>".to_string()),
+            },
+            "engine" => match args {
+                ["mcts"] => {
+                    self.alphabeta = false;
+                    Ok(())
+                }
+                ["alphabeta"] => {
+                    self.alphabeta = true;
+                    self.tree = None;
+                    Ok(())
+                }
+                _ => Err("usage: engine <mcts|alphabeta>".to_string()),
+            },
+            "selection" => self.set_selection(args),
+            "playout" => self.set_playout(args),
+            "hash" => match args {
+                [entries] => entries
+                    .parse()
+                    .map(|entries| self.policies = self.policies.clone().with_transpositions(entries))
+                    .map_err(|_| format!("{} is not a number of entries", entries)),
+                _ => Err("usage: hash <entries>".to_string()),
+            },
+            "weights" => match args {
+                [path] => Weights::load(path).map_err(|error| error.to_string()).map(|weights| {
+                    self.weights = weights;
+                    // scores stored under the old weights no longer hold
+                    self.alphabeta_table.lock().unwrap().clear();
+                }),
+                _ => Err("usage: weights <file>".to_string()),
+            },
+            "show" => {
+                send(&self.output, board_diagram(&self.game).trim_end());
+                send(&self.output, &format!("position {}", self.game.to_position()));
+                Ok(())
+            }
+            "legal" => {
+                send(&self.output, &moves_to_string(&self.game.get_legal_moves()));
+                Ok(())
+            }
+            "quit" => return false,
+            _ => Err(format!("unknown command {}", command)),
+        };
+
+        if let Err(message) = result {
+            send(&self.output, &format!("error {}", message));
+        }
+
+        true
+    }
+}
+
+pub fn run<R: BufRead, W: Write + Send + 'static>(input: R, output: W) -> io::Result<()> {
+    let mut engine = Engine {
+        game: Game::new_basic(),
+        output: Arc::new(Mutex::new(output)),
+        search: None,
+        tree: None,
+        alphabeta: false,
+        alphabeta_table: Arc::new(Mutex::new(TranspositionTable::new(DEFAULT_TABLE_ENTRIES))),
+        weights: Weights::default(),
+        policies: Policies::default(),
+        threads: 1,
+    };
+
+    for line in input.lines() {
+        if !engine.handle(&line?) {
+            break;
+        }
+    }
+
+    engine.stop_search();
+    Ok(())
+}
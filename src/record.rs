@@ -0,0 +1,331 @@
+//! Game records: a starting position, the moves played from it, the result and some
+//! descriptive metadata, stored in a small PGN-like text format:
+//!
+//! ```text
+//! [White "mcts 1000"]
+//! [Black "random"]
+//! [Date "2022-08-01"]
+//! [TimeControl "1000 iterations"]
+//! [Position "BBBBB/BBBBBB/2BBB2/8/9/8/2WWW2/WWWWWW/WWWWW w 0 0 0"]
+//! [Result "1-0"]
+//!
+//! 1. A1B2 I5H5 2. B1C2 H5G5 1-0
+//! ```
+//!
+//! Moves use the notation from `notation`, and every move is checked against
+//! `Game::get_legal_moves` when it is recorded or read. A `Result` tag and the result at the
+//! end of the moves must agree, and a game that is over must carry its real result.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::game::{Game, Move, Player};
+use crate::notation::ParseMoveError;
+use crate::position::ParsePositionError;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    Unfinished,
+}
+
+impl GameResult {
+    pub fn from_game(game: &Game) -> Self {
+        match (game.game_over, game.winner) {
+            (false, _) => GameResult::Unfinished,
+            (true, Some(Player::White)) => GameResult::WhiteWins,
+            (true, Some(Player::Black)) => GameResult::BlackWins,
+            (true, None) => GameResult::Draw,
+        }
+    }
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let result = match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+            GameResult::Unfinished => "*",
+        };
+        write!(f, "{}", result)
+    }
+}
+
+impl FromStr for GameResult {
+    type Err = RecordError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1-0" => Ok(GameResult::WhiteWins),
+            "0-1" => Ok(GameResult::BlackWins),
+            "1/2-1/2" => Ok(GameResult::Draw),
+            "*" => Ok(GameResult::Unfinished),
+            _ => Err(RecordError::InvalidResult(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RecordError {
+    Io(io::Error),
+    InvalidTag(String),
+    InvalidPosition(ParsePositionError),
+    InvalidMove { ply: usize, error: ParseMoveError },
+    IllegalMove { ply: usize, attempted: Move },
+    MoveAfterGameOver { ply: usize },
+    InvalidResult(String),
+    ResultMismatch { recorded: GameResult, actual: GameResult },
+    ConflictingResults { tag: GameResult, movetext: GameResult },
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecordError::Io(error) => write!(f, "{}", error),
+            RecordError::InvalidTag(line) => write!(f, "malformed tag line {:?}", line),
+            RecordError::InvalidPosition(error) => write!(f, "invalid starting position: {}", error),
+            RecordError::InvalidMove { ply, error } => write!(f, "ply {}: {}", ply + 1, error),
+            RecordError::IllegalMove { ply, attempted } => {
+                write!(f, "ply {}: {} is not a legal move", ply + 1, attempted)
+            }
+            RecordError::MoveAfterGameOver { ply } => {
+                write!(f, "ply {}: the game is already over", ply + 1)
+            }
+            RecordError::InvalidResult(result) => write!(f, "{:?} is not a valid result", result),
+            RecordError::ResultMismatch { recorded, actual } => write!(
+                f,
+                "recorded result {} does not match the final position ({})",
+                recorded, actual
+            ),
+            RecordError::ConflictingResults { tag, movetext } => {
+                write!(f, "the Result tag says {} but the moves end with {}", tag, movetext)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+impl From<io::Error> for RecordError {
+    fn from(error: io::Error) -> Self {
+        RecordError::Io(error)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GameRecord {
+    pub white: Option<String>,
+    pub black: Option<String>,
+    pub date: Option<String>,
+    pub time_control: Option<String>,
+    // the moves are only changed through `push` and `pop`, which keep `current` in step
+    start: Game,
+    moves: Vec<Move>,
+    pub result: GameResult,
+    // the position after the last move, so that recording a move does not replay the game
+    current: Game,
+}
+
+impl GameRecord {
+    pub fn new(start: Game) -> Self {
+        GameRecord {
+            white: None,
+            black: None,
+            date: None,
+            time_control: None,
+            start,
+            moves: Vec::new(),
+            result: GameResult::from_game(&start),
+            current: start,
+        }
+    }
+
+    pub fn start(&self) -> Game {
+        self.start
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    // checks a move against the current final position before recording it
+    pub fn push(&mut self, next_move: Move) -> Result<Game, RecordError> {
+        let next_state = Self::apply(&self.current, next_move, self.moves.len())?;
+
+        self.moves.push(next_move);
+        self.result = GameResult::from_game(&next_state);
+        self.current = next_state;
+
+        Ok(next_state)
+    }
+
+    // takes back the last move, reopening the game if it had been decided
+    pub fn pop(&mut self) -> Option<Move> {
+        let last_move = self.moves.pop()?;
+        self.result = GameResult::Unfinished;
+        // the moves left were checked when they were pushed
+        self.current = self.moves.iter().fold(self.start, |game, next_move| game.make_move(next_move));
+
+        Some(last_move)
+    }
+
+    // every position of the game, starting with `start`
+    pub fn replay(&self) -> Vec<Game> {
+        let mut positions = vec![self.start];
+        // the moves were checked when they were pushed
+        for next_move in &self.moves {
+            positions.push(positions.last().unwrap().make_move(next_move));
+        }
+
+        positions
+    }
+
+    pub fn final_position(&self) -> Game {
+        self.current
+    }
+
+    // a finished game must carry its real result, an unfinished one may have been resigned or adjourned
+    pub fn validate(&self) -> Result<(), RecordError> {
+        let actual = GameResult::from_game(&self.current);
+
+        if actual != GameResult::Unfinished && actual != self.result {
+            return Err(RecordError::ResultMismatch { recorded: self.result, actual });
+        }
+
+        Ok(())
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), RecordError> {
+        fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, RecordError> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    fn apply(game: &Game, next_move: Move, ply: usize) -> Result<Game, RecordError> {
+        if game.game_over {
+            return Err(RecordError::MoveAfterGameOver { ply });
+        }
+        if !game.get_legal_moves().contains(&next_move) {
+            return Err(RecordError::IllegalMove { ply, attempted: next_move });
+        }
+
+        Ok(game.make_move(&next_move))
+    }
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let tags = [
+            ("White", &self.white),
+            ("Black", &self.black),
+            ("Date", &self.date),
+            ("TimeControl", &self.time_control),
+        ];
+        for (name, value) in tags {
+            if let Some(value) = value {
+                writeln!(f, "[{} \"{}\"]", name, value)?;
+            }
+        }
+        writeln!(f, "[Position \"{}\"]", self.start.to_position())?;
+        writeln!(f, "[Result \"{}\"]", self.result)?;
+        writeln!(f)?;
+
+        let mut line = String::new();
+        for (ply, next_move) in self.moves.iter().enumerate() {
+            let number = self.start.move_number as usize + ply;
+            let token = match (ply, number % 2) {
+                (_, 0) => format!("{}. {}", number / 2 + 1, next_move),
+                (0, _) => format!("{}... {}", number / 2 + 1, next_move),
+                _ => next_move.to_string(),
+            };
+            if !line.is_empty() && line.len() + token.len() >= 80 {
+                writeln!(f, "{}", line.trim_end())?;
+                line.clear();
+            }
+            line.push_str(&token);
+            line.push(' ');
+        }
+        line.push_str(&self.result.to_string());
+
+        writeln!(f, "{}", line)
+    }
+}
+
+impl FromStr for GameRecord {
+    type Err = RecordError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut record = GameRecord::new(Game::new_basic());
+        let mut tag_result: Option<GameResult> = None;
+        let mut movetext_result: Option<GameResult> = None;
+        let mut moves = Vec::new();
+        let mut movetext = String::new();
+
+        for line in s.lines() {
+            let line = line.trim();
+
+            if !line.starts_with('[') {
+                movetext.push_str(line);
+                movetext.push(' ');
+                continue;
+            }
+
+            let invalid = || RecordError::InvalidTag(line.to_string());
+            let inner = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')).ok_or_else(invalid)?;
+            let (name, value) = inner.split_once(' ').ok_or_else(invalid)?;
+            let value = value.trim().strip_prefix('"').and_then(|v| v.strip_suffix('"')).ok_or_else(invalid)?;
+
+            match name {
+                "White" => record.white = Some(value.to_string()),
+                "Black" => record.black = Some(value.to_string()),
+                "Date" => record.date = Some(value.to_string()),
+                "TimeControl" => record.time_control = Some(value.to_string()),
+                "Position" => record.start = Game::from_position(value).map_err(RecordError::InvalidPosition)?,
+                "Result" => tag_result = Some(value.parse()?),
+                _ => (),
+            }
+        }
+
+        for token in movetext.split_whitespace() {
+            if token.ends_with('.') {
+                continue;
+            }
+            if let Ok(result) = token.parse::<GameResult>() {
+                movetext_result = Some(result);
+                continue;
+            }
+
+            let ply = moves.len();
+            let next_move = token
+                .parse::<Move>()
+                .map_err(|error| RecordError::InvalidMove { ply, error })?;
+            moves.push(next_move);
+        }
+
+        record.current = record.start;
+        for next_move in moves {
+            record.push(next_move)?;
+        }
+
+        if let (Some(tag), Some(movetext)) = (tag_result, movetext_result) {
+            if tag != movetext {
+                return Err(RecordError::ConflictingResults { tag, movetext });
+            }
+        }
+        if let Some(result) = tag_result.or(movetext_result) {
+            record.result = result;
+        }
+        record.validate()?;
+
+        Ok(record)
+    }
+}
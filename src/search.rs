@@ -0,0 +1,316 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rayon::prelude::*;
+use reduce::Reduce;
+
+use crate::alphabeta::alphabeta_search_with_limits;
+use crate::eval::Weights;
+use crate::game::{Game, Move};
+use crate::game::Player;
+use crate::node::ArenaTree;
+use crate::parallel::shared_tree_search;
+use crate::playout::{GreedyPlayout, PlayoutPolicy};
+use crate::selection::{SelectionPolicy, Ucb1};
+use crate::transposition::{NodeStats, TranspositionTable};
+
+// how often the early stopping rule looks at the root, it is not free on wide roots
+pub(crate) const DECIDED_CHECK_INTERVAL: usize = 64;
+
+// how many moves past the last searched position a kept tree is searched for the new one,
+// enough to cover the engine's own move and the opponent's reply
+pub(crate) const REUSE_DEPTH: usize = 2;
+
+// When a search ends. Every limit that is set applies and the search ends at the first one
+// reached; with no limits at all the search runs until `stop` is raised.
+#[derive(Debug, Clone, Default)]
+pub struct SearchLimits {
+    pub time: Option<Duration>,
+    pub nodes: Option<usize>,
+    pub iterations: Option<usize>,
+    // end once the most visited root move can no longer be overtaken in the remaining budget
+    pub stop_when_decided: bool,
+    pub stop: Option<Arc<AtomicBool>>,
+}
+
+// How the search finds its way through the tree, chosen per search.
+#[derive(Clone)]
+pub struct Policies {
+    pub selection: Arc<dyn SelectionPolicy>,
+    pub playout: Arc<dyn PlayoutPolicy>,
+    // playouts longer than this are cut off and called by material, then by evaluation
+    pub max_playout_length: Option<usize>,
+    // statistics shared between transpositions, and between searches holding the same table
+    pub transpositions: Option<Arc<Mutex<TranspositionTable<NodeStats>>>>,
+}
+
+impl Default for Policies {
+    fn default() -> Self {
+        Policies {
+            selection: Arc::new(Ucb1::default()),
+            playout: Arc::new(GreedyPlayout),
+            max_playout_length: None,
+            transpositions: None,
+        }
+    }
+}
+
+impl Policies {
+    // these policies with a fresh transposition table of `entries` entries, or none for 0
+    pub fn with_transpositions(self, entries: usize) -> Self {
+        let transpositions = (entries > 0).then(|| Arc::new(Mutex::new(TranspositionTable::new(entries))));
+        Policies { transpositions, ..self }
+    }
+
+    // these policies with an empty table the size of the current one, for searches that
+    // should learn nothing from those before them, such as those of another game
+    pub fn with_fresh_transpositions(&self) -> Self {
+        let entries = self.transpositions.as_ref().map_or(0, |table| table.lock().unwrap().len());
+        self.clone().with_transpositions(entries)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SearchProgress {
+    pub iterations: usize,
+    // nodes added by this search, not counting any kept from an earlier one
+    pub nodes: usize,
+    pub elapsed: Duration,
+}
+
+impl SearchLimits {
+    pub fn iterations(iterations: usize) -> Self {
+        SearchLimits { iterations: Some(iterations), ..Default::default() }
+    }
+
+    pub fn time(time: Duration) -> Self {
+        SearchLimits { time: Some(time), ..Default::default() }
+    }
+
+    pub fn nodes(nodes: usize) -> Self {
+        SearchLimits { nodes: Some(nodes), ..Default::default() }
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stop.as_ref().is_some_and(|stop| stop.load(Ordering::Relaxed))
+    }
+
+    pub fn reached(&self, progress: &SearchProgress) -> bool {
+        self.is_stopped()
+            || self.iterations.is_some_and(|limit| progress.iterations >= limit)
+            || self.nodes.is_some_and(|limit| progress.nodes >= limit)
+            || self.time.is_some_and(|limit| progress.elapsed >= limit)
+    }
+
+    // an upper bound on the iterations still to come, if any limit gives one
+    pub(crate) fn remaining_iterations(&self, progress: &SearchProgress) -> Option<usize> {
+        let by_count = self.iterations.map(|limit| limit.saturating_sub(progress.iterations));
+
+        let by_time = self.time.map(|limit| {
+            let rate = progress.iterations as f64 / progress.elapsed.as_secs_f64().max(1e-6);
+            (rate * limit.saturating_sub(progress.elapsed).as_secs_f64()).ceil() as usize
+        });
+
+        match (by_count, by_time) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+// `visits` are those of the root's children, empty while the root is unexpanded
+pub(crate) fn is_decided(mut visits: Vec<i32>, remaining: usize) -> bool {
+    if visits.is_empty() {
+        return false;
+    }
+    if visits.len() < 2 {
+        return true;
+    }
+    visits.sort_unstable_by(|a, b| b.cmp(a));
+
+    (visits[0] - visits[1]) as usize > remaining
+}
+
+fn root_visits(arena: &ArenaTree) -> Vec<i32> {
+    let root = &arena.nodes[0];
+    match (root.first_child_id, root.last_child_id) {
+        (Some(first), Some(last)) => (first..last).map(|child| arena.nodes[child].visits).collect(),
+        _ => Vec::new(),
+    }
+}
+
+pub fn mcts_iteration(arena: &mut ArenaTree, policies: &Policies) {
+    let Some(transpositions) = &policies.transpositions else {
+        let selected: usize = arena.select_step(0, policies.selection.as_ref());
+        let expanded: usize = arena.expand_step(selected);
+        let result: Option<Player> = arena.playout(expanded, policies.playout.as_ref(), policies.max_playout_length);
+        arena.backpropagate(expanded, result);
+        return;
+    };
+
+    let selected = arena.select_step_with_table(0, policies.selection.as_ref(), Some(&transpositions.lock().unwrap()));
+    let expanded: usize = arena.expand_step(selected);
+    let result: Option<Player> = arena.playout(expanded, policies.playout.as_ref(), policies.max_playout_length);
+    arena.backpropagate_with_table(expanded, result, &mut transpositions.lock().unwrap());
+}
+
+// Keeps searching an existing tree until one of the limits is reached, calling `on_progress`
+// after every iteration. At least one iteration is always run so the root has children.
+pub fn mcts_search_tree<F>(
+    arena: &mut ArenaTree,
+    limits: &SearchLimits,
+    policies: &Policies,
+    mut on_progress: F,
+) -> SearchProgress
+where
+    F: FnMut(&ArenaTree, &SearchProgress),
+{
+    let start = Instant::now();
+    let start_len = arena.nodes.len();
+    let mut progress = SearchProgress { iterations: 0, nodes: 0, elapsed: Duration::ZERO };
+
+    loop {
+        mcts_iteration(arena, policies);
+
+        progress.iterations += 1;
+        progress.nodes = arena.nodes.len() - start_len;
+        progress.elapsed = start.elapsed();
+        on_progress(arena, &progress);
+
+        if limits.reached(&progress) {
+            break;
+        }
+
+        if limits.stop_when_decided && progress.iterations.is_multiple_of(DECIDED_CHECK_INTERVAL) {
+            if let Some(remaining) = limits.remaining_iterations(&progress) {
+                if is_decided(root_visits(arena), remaining) {
+                    break;
+                }
+            }
+        }
+    }
+
+    progress
+}
+
+// `tree` re-rooted at `root` if it reached that position, otherwise a fresh tree
+pub(crate) fn reuse_tree(tree: Option<ArenaTree>, root: Game) -> ArenaTree {
+    let reused = tree.and_then(|mut tree| tree.reroot(&root, REUSE_DEPTH).then_some(tree));
+
+    reused.unwrap_or_else(|| {
+        let mut arena = ArenaTree{nodes : Vec::new()};
+        arena.new_node(root, None, None);
+        arena
+    })
+}
+
+pub fn mcts_search_with_limits(root: Game, limits: &SearchLimits, policies: &Policies) -> ArenaTree {
+    let mut arena = ArenaTree{nodes : Vec::new()};
+
+    arena.new_node(root, None, None);
+    mcts_search_tree(&mut arena, limits, policies, |_, _| ());
+
+    arena
+}
+
+pub fn mcts_search(root: Game, n_think : i32) -> ArenaTree {
+    mcts_search_with_limits(root, &SearchLimits::iterations(n_think.max(1) as usize), &Policies::default())
+}
+
+// Runs n_threads independent searches from the same root and merges them into one tree.
+// A time limit is shared, so searches that only get a core late still finish on time.
+pub fn parallel_mcts_search_with_limits(
+    root: Game,
+    limits: &SearchLimits,
+    policies: &Policies,
+    n_threads: usize,
+) -> ArenaTree {
+    let deadline = limits.time.map(|time| Instant::now() + time);
+
+    let arena_vec : Vec<ArenaTree> = (0..n_threads.max(1)).
+        into_par_iter().
+        map(|_| {
+            let mut limits = limits.clone();
+            limits.time = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+            mcts_search_with_limits(root, &limits, policies)
+        }).
+        collect();
+
+    Reduce::reduce(arena_vec.into_iter(), | a,b | a.merge_trees(&b)).unwrap()
+}
+
+pub fn parallel_mcts_search(root: Game, n_think : i32, n_threads: usize) -> ArenaTree {
+    let limits = SearchLimits::iterations(n_think.max(1) as usize);
+    parallel_mcts_search_with_limits(root, &limits, &Policies::default(), n_threads)
+}
+
+// The search algorithms the engine can play with.
+#[derive(Clone)]
+pub enum Algorithm {
+    Mcts(Policies),
+    AlphaBeta(Weights),
+}
+
+impl Default for Algorithm {
+    fn default() -> Self {
+        Algorithm::Mcts(Policies::default())
+    }
+}
+
+// what either algorithm makes of a position
+#[derive(Debug, Clone)]
+pub struct SearchOutcome {
+    pub best_move: Move,
+    pub principal_variation: Vec<Move>,
+    // the estimated chance of winning after `best_move` for the player making it
+    pub win_rate: f32,
+}
+
+impl Algorithm {
+    // the same algorithm with nothing kept from earlier searches, to start a new game with
+    pub fn for_new_game(&self) -> Algorithm {
+        match self {
+            Algorithm::Mcts(policies) => Algorithm::Mcts(policies.with_fresh_transpositions()),
+            Algorithm::AlphaBeta(weights) => Algorithm::AlphaBeta(*weights),
+        }
+    }
+
+    // Searches a position that is not over. MCTS searches a shared tree on n_threads
+    // threads, alpha-beta always runs on one.
+    pub fn search(&self, root: Game, limits: &SearchLimits, n_threads: usize) -> SearchOutcome {
+        self.search_with_tree(root, limits, n_threads, &mut None)
+    }
+
+    // Like `search`, but MCTS starts from `tree` where it reached `root` and leaves its own
+    // tree there for the next move, so a game loop keeps the statistics from move to move.
+    pub fn search_with_tree(
+        &self,
+        root: Game,
+        limits: &SearchLimits,
+        n_threads: usize,
+        tree: &mut Option<ArenaTree>,
+    ) -> SearchOutcome {
+        match self {
+            Algorithm::Mcts(policies) => {
+                let arena = reuse_tree(tree.take(), root);
+                let (arena, _) = shared_tree_search(arena, limits, policies, n_threads, |_, _| ());
+                let outcome = SearchOutcome {
+                    best_move: arena.best_move().unwrap(),
+                    principal_variation: arena.principal_variation(),
+                    win_rate: arena.win_rate(arena.reccomend()),
+                };
+                *tree = Some(arena);
+                outcome
+            }
+            Algorithm::AlphaBeta(weights) => {
+                let result = alphabeta_search_with_limits(root, limits, weights);
+                SearchOutcome {
+                    best_move: result.best_move.unwrap(),
+                    win_rate: result.win_rate(),
+                    principal_variation: result.principal_variation,
+                }
+            }
+        }
+    }
+}
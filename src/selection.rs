@@ -0,0 +1,199 @@
+//! Policies for the select step of MCTS, deciding which child of a fully tried node the
+//! search descends into.
+//!
+//! Scores are always from the point of view of the player who moved into the child, which
+//! is how `Node::wins` is counted.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use rand_distr::{Beta, Distribution};
+
+use crate::eval::center_distance;
+use crate::game::{Game, Player};
+
+// What a policy gets to see of each child. Every candidate has been visited at least once,
+// and with tree parallel search `visits` includes the playouts still in flight.
+#[derive(Debug, Clone, Copy)]
+pub struct Candidate<'a> {
+    pub visits: f32,
+    pub wins: f32,
+    pub game_state: &'a Game,
+}
+
+impl Candidate<'_> {
+    fn mean(&self) -> f32 {
+        self.wins / self.visits
+    }
+}
+
+pub trait SelectionPolicy: Send + Sync {
+    // the index into `children` of the child to descend into
+    fn select(&self, parent_visits: f32, children: &[Candidate]) -> usize;
+}
+
+fn argmax<F: FnMut(usize, &Candidate) -> f32>(children: &[Candidate], mut score: F) -> usize {
+    let mut best = 0;
+    let mut best_score = f32::MIN;
+
+    for (index, child) in children.iter().enumerate() {
+        let score = score(index, child);
+        if score > best_score {
+            best = index;
+            best_score = score;
+        }
+    }
+
+    best
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Ucb1 {
+    pub exploration: f32,
+}
+
+impl Default for Ucb1 {
+    fn default() -> Self {
+        Ucb1 { exploration: 1.41 }
+    }
+}
+
+impl SelectionPolicy for Ucb1 {
+    fn select(&self, parent_visits: f32, children: &[Candidate]) -> usize {
+        let log_parent = parent_visits.ln();
+        argmax(children, |_, child| child.mean() + self.exploration * (log_parent / child.visits).sqrt())
+    }
+}
+
+// UCB1 with the exploration term bounded by an estimate of each child's variance, from
+// Auer, Cesa-Bianchi and Fischer (2002)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ucb1Tuned;
+
+impl SelectionPolicy for Ucb1Tuned {
+    fn select(&self, parent_visits: f32, children: &[Candidate]) -> usize {
+        let log_parent = parent_visits.ln();
+
+        argmax(children, |_, child| {
+            let mean = child.mean();
+            // results are 0 or 1, so the mean of the squares is the mean
+            let variance = mean - mean * mean + (2.0 * log_parent / child.visits).sqrt();
+            mean + (log_parent / child.visits * variance.min(0.25)).sqrt()
+        })
+    }
+}
+
+// The prior weight of a child position; only its size relative to the siblings matters.
+pub type Prior = fn(&Game) -> f32;
+
+// Favours moves that push a marble off the board most, then moves that bring the mover's
+// marbles towards the centre. Siblings differ only by the move leading to them, so the
+// material and centre distance of the child alone rank them.
+pub fn capture_prior(child: &Game) -> f32 {
+    let mover = child.player.opponent();
+    let (own, theirs) = match mover {
+        Player::White => (child.white_pieces, child.black_pieces),
+        Player::Black => (child.black_pieces, child.white_pieces),
+    };
+
+    (3.0 * (own - theirs) as f32 - 0.5 * center_distance(child, mover) as f32).exp()
+}
+
+// The AlphaZero style rule, spreading exploration over children by their prior
+#[derive(Clone, Copy)]
+pub struct Puct {
+    pub exploration: f32,
+    pub prior: Prior,
+}
+
+impl Default for Puct {
+    fn default() -> Self {
+        Puct { exploration: 1.5, prior: capture_prior }
+    }
+}
+
+impl fmt::Debug for Puct {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Puct").field("exploration", &self.exploration).finish_non_exhaustive()
+    }
+}
+
+impl SelectionPolicy for Puct {
+    fn select(&self, parent_visits: f32, children: &[Candidate]) -> usize {
+        let priors: Vec<f32> = children.iter().map(|child| (self.prior)(child.game_state).max(0.0)).collect();
+        let total: f32 = priors.iter().sum();
+        let sqrt_parent = parent_visits.sqrt();
+
+        argmax(children, |index, child| {
+            let prior = if total > 0.0 { priors[index] / total } else { 1.0 / children.len() as f32 };
+            child.mean() + self.exploration * prior * sqrt_parent / (1.0 + child.visits)
+        })
+    }
+}
+
+// Draws a win rate for every child from its Beta posterior and takes the best draw
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Thompson;
+
+impl SelectionPolicy for Thompson {
+    fn select(&self, _parent_visits: f32, children: &[Candidate]) -> usize {
+        let mut rng = rand::thread_rng();
+
+        argmax(children, |_, child| {
+            let losses = (child.visits - child.wins).max(0.0);
+            Beta::new(child.wins + 1.0, losses + 1.0).unwrap().sample(&mut rng)
+        })
+    }
+}
+
+// The built in policies by name, for command lines and the engine protocol
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionKind {
+    Ucb1,
+    Ucb1Tuned,
+    Puct,
+    Thompson,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseSelectionError(String);
+
+impl fmt::Display for ParseSelectionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown selection policy {:?}, expected ucb1, ucb1-tuned, puct or thompson", self.0)
+    }
+}
+
+impl std::error::Error for ParseSelectionError {}
+
+impl FromStr for SelectionKind {
+    type Err = ParseSelectionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "ucb1" => Ok(SelectionKind::Ucb1),
+            "ucb1-tuned" => Ok(SelectionKind::Ucb1Tuned),
+            "puct" => Ok(SelectionKind::Puct),
+            "thompson" => Ok(SelectionKind::Thompson),
+            _ => Err(ParseSelectionError(s.to_string())),
+        }
+    }
+}
+
+impl SelectionKind {
+    // the policy with its default settings, or the given exploration constant where it has one
+    pub fn policy(self, exploration: Option<f32>) -> Arc<dyn SelectionPolicy> {
+        match self {
+            SelectionKind::Ucb1 => {
+                Arc::new(Ucb1 { exploration: exploration.unwrap_or(Ucb1::default().exploration) })
+            }
+            SelectionKind::Ucb1Tuned => Arc::new(Ucb1Tuned),
+            SelectionKind::Puct => Arc::new(Puct {
+                exploration: exploration.unwrap_or(Puct::default().exploration),
+                ..Puct::default()
+            }),
+            SelectionKind::Thompson => Arc::new(Thompson),
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! A fixed size transposition table, keyed by `Game::hash`.
+//!
+//! Every position maps to a single slot. When two positions compete for one, the newcomer
+//! replaces entries from earlier searches and entries of no higher priority, where priority
+//! is whatever the user makes it: search depth for alpha-beta, visits for MCTS.
+//!
+//! MCTS keeps `NodeStats` here so that every node for a position sees the playouts of all
+//! the lines leading to it, turning the tree into a graph as far as selection is concerned.
+
+use crate::game::{Game, Player};
+
+#[derive(Debug, Clone, Copy)]
+struct Entry<T> {
+    key: u64,
+    priority: u32,
+    generation: u8,
+    value: T,
+}
+
+#[derive(Debug, Clone)]
+pub struct TranspositionTable<T> {
+    entries: Vec<Option<Entry<T>>>,
+    generation: u8,
+}
+
+impl<T: Copy> TranspositionTable<T> {
+    // a table of `size` entries, rounded up to a power of two
+    pub fn new(size: usize) -> Self {
+        TranspositionTable { entries: vec![None; size.max(1).next_power_of_two()], generation: 0 }
+    }
+
+    fn slot(&self, key: u64) -> usize {
+        key as usize & (self.entries.len() - 1)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // marks everything stored so far as old, to be replaced first
+    pub fn new_search(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|entry| *entry = None);
+    }
+
+    pub fn get(&self, game: &Game) -> Option<&T> {
+        match &self.entries[self.slot(game.hash())] {
+            Some(entry) if entry.key == game.hash() => Some(&entry.value),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, game: &Game) -> Option<&mut T> {
+        let slot = self.slot(game.hash());
+        match &mut self.entries[slot] {
+            Some(entry) if entry.key == game.hash() => Some(&mut entry.value),
+            _ => None,
+        }
+    }
+
+    // Stores `value` for `game` unless the slot holds a different position from this search
+    // with a higher priority. Returns whether it was stored.
+    pub fn insert(&mut self, game: &Game, value: T, priority: u32) -> bool {
+        let slot = self.slot(game.hash());
+        let replace = match &self.entries[slot] {
+            None => true,
+            Some(entry) => {
+                entry.key == game.hash() || entry.generation != self.generation || entry.priority <= priority
+            }
+        };
+
+        if replace {
+            self.entries[slot] = Some(Entry { key: game.hash(), priority, generation: self.generation, value });
+        }
+        replace
+    }
+
+    // Applies `f` to the value stored for `game`, starting from `default` if there is none,
+    // and stores the result with the priority `f` returns, under the same rule as `insert`.
+    pub fn update<F: FnOnce(&mut T) -> u32>(&mut self, game: &Game, default: T, f: F) -> bool {
+        let mut value = self.get(game).copied().unwrap_or(default);
+        let priority = f(&mut value);
+        self.insert(game, value, priority)
+    }
+}
+
+// The playouts through a position over every line that reaches it, counted like `Node`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NodeStats {
+    pub visits: i32,
+    pub wins: i32,
+}
+
+impl NodeStats {
+    // adds a playout won by `winner` to `game`'s statistics
+    pub fn record(table: &mut TranspositionTable<NodeStats>, game: &Game, winner: Option<Player>) {
+        table.update(game, NodeStats::default(), |stats| {
+            stats.visits += 1;
+            if winner.is_some_and(|winner| winner != game.player) {
+                stats.wins += 1;
+            }
+            stats.visits as u32
+        });
+    }
+
+    // The statistics to select by for a node with `visits` and `wins` of its own: the
+    // table's, unless its entry was lost to another position and it knows less than the node.
+    pub fn lookup(table: Option<&TranspositionTable<NodeStats>>, game: &Game, visits: i32, wins: i32) -> NodeStats {
+        match table.and_then(|table| table.get(game)) {
+            Some(stats) if stats.visits >= visits => *stats,
+            _ => NodeStats { visits, wins },
+        }
+    }
+}
@@ -0,0 +1,124 @@
+// Alpha-beta finds forced wins, keeps mate distances straight in its table, learns from the
+// table between searches and stops where its limits say.
+
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use abalone::alphabeta::{
+    alphabeta_search_with_limits, alphabeta_search_with_table, score_from_table, score_to_table,
+    DEFAULT_TABLE_ENTRIES,
+};
+use abalone::eval::WIN_SCORE;
+use abalone::transposition::TranspositionTable;
+use abalone::{alphabeta_search, Game, Player, SearchLimits, Space, Weights};
+
+// Black is down to nine marbles, one of them alone on I9 with two white marbles lined up
+// behind it on G7 and H8, so white wins by pushing it off.
+fn win_in_one() -> Game {
+    let mut game = Game::new_basic();
+    for x in 0..9 {
+        for y in 0..9 {
+            game.set(x, y, Space::Empty);
+        }
+    }
+
+    let white = [(4, 6), (4, 7), (8, 4), (8, 5), (8, 6), (8, 7), (8, 8), (7, 3), (7, 4)];
+    let black = [(4, 8), (0, 0), (0, 1), (0, 2), (0, 3), (1, 0), (1, 1), (1, 2), (1, 3)];
+    for (x, y) in white {
+        game.set(x, y, Space::Occupied(Player::White));
+    }
+    for (x, y) in black {
+        game.set(x, y, Space::Occupied(Player::Black));
+    }
+    (game.white_pieces, game.black_pieces) = (9, 9);
+    game
+}
+
+#[test]
+fn finds_a_win_in_one_and_a_capture() {
+    let game = win_in_one();
+
+    for depth in 1..=3 {
+        let result = alphabeta_search(game, depth);
+        let next_state = game.make_move(&result.best_move.unwrap());
+        assert_eq!(next_state.winner, Some(Player::White), "depth {}", depth);
+        assert_eq!(result.score, WIN_SCORE - 1);
+        assert_eq!(result.win_rate(), 1.0);
+    }
+
+
+    // with a marble to spare black survives the push, but it is still the move to play
+    let mut capture = game;
+    capture.set(2, 0, Space::Occupied(Player::Black));
+    capture.black_pieces += 1;
+    for depth in 1..=2 {
+        let result = alphabeta_search(capture, depth);
+        let next_state = capture.make_move(&result.best_move.unwrap());
+        assert_eq!((next_state.black_pieces, next_state.game_over), (9, false), "depth {}", depth);
+        assert!(result.score > Weights::default().evaluate(&capture) + 500);
+    }
+}
+
+#[test]
+fn mate_distances_in_the_table() {
+    // a win five plies from the root, found three plies down, is two plies from there
+    assert_eq!(score_to_table(WIN_SCORE - 5, 3), WIN_SCORE - 2);
+    assert_eq!(score_from_table(WIN_SCORE - 2, 3), WIN_SCORE - 5);
+    // and reached one ply down in another search, three plies from its root
+    assert_eq!(score_from_table(WIN_SCORE - 2, 1), WIN_SCORE - 3);
+
+    assert_eq!(score_to_table(-(WIN_SCORE - 5), 3), -(WIN_SCORE - 2));
+    assert_eq!(score_from_table(-(WIN_SCORE - 2), 3), -(WIN_SCORE - 5));
+
+    // everything else is stored as it is
+    for score in [0, 1234, -1234, 50_000] {
+        for ply in [0, 1, 7] {
+            assert_eq!(score_to_table(score, ply), score);
+            assert_eq!(score_from_table(score_to_table(score, ply), ply), score);
+        }
+    }
+}
+
+#[test]
+fn reuses_the_table() {
+    let game = Game::new_belgian_daisy();
+    let limits = SearchLimits::iterations(3);
+    let mut table = TranspositionTable::new(DEFAULT_TABLE_ENTRIES);
+
+    let first = alphabeta_search_with_table(game, &limits, &Weights::default(), &mut table, |_| ());
+    let second = alphabeta_search_with_table(game, &limits, &Weights::default(), &mut table, |_| ());
+    assert_eq!(second.best_move, first.best_move);
+    assert_eq!(second.score, first.score);
+    assert!(second.progress.nodes < first.progress.nodes, "{} {}", second.progress.nodes, first.progress.nodes);
+}
+
+#[test]
+fn depth_limit() {
+    let mut depths = Vec::new();
+    let limits = SearchLimits::iterations(3);
+    let mut table = TranspositionTable::new(1 << 12);
+    let result =
+        alphabeta_search_with_table(Game::new_basic(), &limits, &Weights::default(), &mut table, |result| {
+            depths.push(result.depth)
+        });
+
+    assert_eq!(depths, [1, 2, 3]);
+    assert_eq!((result.depth, result.progress.iterations), (3, 3));
+    assert_eq!(result.principal_variation.len(), 3);
+}
+
+#[test]
+fn time_and_stop_limits() {
+    let start = Instant::now();
+    let limits = SearchLimits::time(Duration::from_millis(100));
+    let result = alphabeta_search_with_limits(Game::new_belgian_daisy(), &limits, &Weights::default());
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert!(result.best_move.is_some() && result.depth >= 1);
+
+    // stopped before it starts, the first depth is still searched so there is a move
+    let limits = SearchLimits { stop: Some(Arc::new(AtomicBool::new(true))), ..Default::default() };
+    let result = alphabeta_search_with_limits(Game::new_belgian_daisy(), &limits, &Weights::default());
+    assert_eq!(result.depth, 1);
+    assert!(result.best_move.is_some());
+}
@@ -0,0 +1,34 @@
+// Single cells are read and written in place, and writes off the board change nothing.
+
+use abalone::{Game, Player, Space};
+
+#[test]
+fn set_and_space() {
+    let mut game = Game::new_basic();
+    let hash = game.hash();
+
+    game.set(4, 4, Space::Occupied(Player::Black));
+    assert_eq!(game.space(4, 4), Space::Occupied(Player::Black));
+    assert_eq!(game.hash(), game.zobrist());
+    assert_ne!(game.hash(), hash);
+
+    game.set(4, 4, Space::Empty);
+    assert_eq!(game.space(4, 4), Space::Empty);
+    assert_eq!(game.hash(), hash);
+}
+
+#[test]
+fn set_off_the_board() {
+    let start = Game::new_basic();
+
+    // past the array, and inside it but off the hexagon
+    for (x, y) in [(20, 0), (0, 20), (9, 4), (4, 9), (usize::MAX, 0), (0, 8), (8, 0)] {
+        for space in [Space::Occupied(Player::White), Space::Occupied(Player::Black), Space::Empty] {
+            let mut game = start;
+            game.set(x, y, space);
+            assert_eq!(game.space(x, y), Space::OutOfBounds);
+            assert_eq!(game.to_position(), start.to_position(), "({}, {})", x, y);
+            assert_eq!(game.hash(), start.hash());
+        }
+    }
+}
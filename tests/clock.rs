@@ -0,0 +1,119 @@
+// Time controls, the clocks they run and the time the engine gives itself per move.
+
+use std::io::Cursor;
+use std::time::Duration;
+
+use abalone::play::{play, PlaySettings};
+use abalone::search::Algorithm;
+use abalone::{Clock, Game, Player, SearchLimits, TimeControl, TimeManager};
+
+fn secs(secs: f64) -> Duration {
+    Duration::from_secs_f64(secs)
+}
+
+fn clock(control: &str) -> Clock {
+    Clock::new(control.parse().unwrap())
+}
+
+#[test]
+fn time_controls_round_trip() {
+    for text in ["300+5", "600+30x3", "10/move", "0.5+0.1"] {
+        assert_eq!(text.parse::<TimeControl>().unwrap().to_string(), text);
+    }
+    for text in ["300", "300+", "+5", "600+30x", "-1/move"] {
+        assert!(text.parse::<TimeControl>().is_err(), "{}", text);
+    }
+}
+
+#[test]
+fn fischer() {
+    let mut clock = clock("10+2");
+
+    assert!(clock.punch(Player::White, secs(3.0)));
+    assert_eq!(clock.white.remaining, secs(9.0));
+    assert_eq!(clock.black.remaining, secs(10.0));
+
+    assert!(clock.punch(Player::White, secs(9.0)));
+    assert_eq!(clock.white.remaining, secs(2.0));
+    assert_eq!(clock.flagged, None);
+
+    assert!(!clock.punch(Player::White, secs(2.5)));
+    assert_eq!(clock.flagged, Some(Player::White));
+}
+
+#[test]
+fn byoyomi() {
+    let mut clock = clock("10+5x3");
+    assert_eq!(clock.available(Player::Black), secs(15.0));
+
+    assert!(clock.punch(Player::Black, secs(4.0)));
+    assert_eq!(clock.black.remaining, secs(6.0));
+
+    // the main time runs out, but the rest of the move fits in a period
+    assert!(clock.punch(Player::Black, secs(8.0)));
+    assert_eq!((clock.black.remaining, clock.black.periods), (Duration::ZERO, 3));
+    assert_eq!(clock.available(Player::Black), secs(5.0));
+
+    // two whole periods are used up
+    assert!(clock.punch(Player::Black, secs(12.0)));
+    assert_eq!(clock.black.periods, 1);
+
+    assert!(!clock.punch(Player::Black, secs(5.0)));
+    assert_eq!(clock.flagged, Some(Player::Black));
+}
+
+#[test]
+fn per_move() {
+    let mut clock = clock("10/move");
+
+    assert!(clock.punch(Player::White, secs(10.0)));
+    assert!(clock.punch(Player::White, secs(1.0)));
+    assert_eq!(clock.white.remaining, secs(10.0));
+
+    assert!(!clock.punch(Player::White, secs(10.5)));
+    assert_eq!(clock.flagged, Some(Player::White));
+}
+
+#[test]
+fn allocate() {
+    let manager = TimeManager::default();
+    let game = Game::new_basic();
+    assert_eq!(manager.moves_to_go(&game), 80);
+
+    // an even share of the time left plus most of the increment
+    assert_eq!(manager.allocate(&clock("300+5"), &game), secs(7.5));
+    assert_eq!(manager.allocate(&clock("600+30x3"), &game), secs(37.5));
+    assert_eq!(manager.allocate(&clock("10/move"), &game), secs(9.95));
+
+    // never closer to the flag than the safety margin
+    assert_eq!(manager.allocate(&clock("1+5"), &game), secs(0.95));
+
+    // late in the game the time is still spread over at least min_moves_to_go moves
+    let mut late = game;
+    late.move_number = 150;
+    assert_eq!(manager.moves_to_go(&late), 20);
+    assert_eq!(manager.allocate(&clock("300+0"), &late), secs(15.0));
+
+    assert_eq!(manager.allocate_from(&game, secs(8.0), Duration::ZERO, Some(4)), secs(2.0));
+}
+
+#[test]
+fn undo_sets_the_clock_back() {
+    let settings = PlaySettings {
+        human: Player::White,
+        limits: SearchLimits::iterations(20),
+        clock: Some("0.2+2".parse().unwrap()),
+        algorithm: Algorithm::default(),
+        threads: 1,
+    };
+    let mut output = Vec::new();
+
+    let record = play(Game::new_basic(), &settings, Cursor::new("A1B2\nundo\nquit\n"), &mut output).unwrap();
+    assert!(record.moves().is_empty());
+
+    let output = String::from_utf8(output).unwrap();
+    let clocks: Vec<&str> = output.lines().filter(|line| line.starts_with("clock:")).collect();
+    assert_eq!(clocks.len(), 3, "{}", output);
+    assert!(clocks[1].starts_with("clock: white 2.2s"), "{}", output);
+    assert_eq!(clocks[2], "clock: white 0.2s, black 0.2s");
+}
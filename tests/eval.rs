@@ -0,0 +1,68 @@
+// The evaluation terms count what they say they count, and weights load from text.
+
+use abalone::eval::{pushes, Weights, WeightsError};
+use abalone::keyvalue::InvalidLine;
+use abalone::{Game, Player, Space};
+
+// a board holding only the given marbles
+fn board(white: &[(usize, usize)], black: &[(usize, usize)]) -> Game {
+    let mut game = Game::new_basic();
+    for x in 0..9 {
+        for y in 0..9 {
+            if game.space(x, y) != Space::OutOfBounds {
+                game.set(x, y, Space::Empty);
+            }
+        }
+    }
+    for &(x, y) in white {
+        game.set(x, y, Space::Occupied(Player::White));
+    }
+    for &(x, y) in black {
+        game.set(x, y, Space::Occupied(Player::Black));
+    }
+    game
+}
+
+#[test]
+fn pushes_from_a_line_of_four() {
+    // A1 to A4 against A5: the front three push, and so do the front two
+    let line = [(4, 0), (4, 1), (4, 2), (4, 3)];
+    assert_eq!(pushes(&board(&line, &[(4, 4)]), Player::White), 2);
+
+    // against two marbles only the front three are enough
+    assert_eq!(pushes(&board(&line, &[(4, 4), (4, 5)]), Player::White), 1);
+
+    // three marbles are never pushed, but one with a gap behind it is
+    assert_eq!(pushes(&board(&line, &[(4, 4), (4, 5), (4, 6)]), Player::White), 0);
+    assert_eq!(pushes(&board(&line, &[(4, 4), (4, 6)]), Player::White), 2);
+    assert_eq!(pushes(&board(&line, &[(4, 4)]), Player::Black), 0);
+}
+
+#[test]
+fn pushes_off_the_edge() {
+    // two marbles push one off the board, from both sides of it
+    let game = board(&[(4, 6), (4, 7)], &[(4, 8), (4, 5), (4, 4)]);
+    assert_eq!(pushes(&game, Player::White), 1);
+    assert_eq!(pushes(&game, Player::Black), 0);
+}
+
+#[test]
+fn weights_from_text() {
+    let weights: Weights = "# only two\n\nmaterial = 900\n  pushing=5  \n".parse().unwrap();
+    assert_eq!(weights, Weights { material: 900, pushing: 5, ..Weights::default() });
+    assert_eq!(weights.to_string().parse::<Weights>().unwrap(), weights);
+
+    let error = |text: &str| text.parse::<Weights>().unwrap_err();
+    assert!(matches!(
+        error("material = 1\ncenter 2"),
+        WeightsError::InvalidLine(InvalidLine { line: 2, text }) if text == "center 2"
+    ));
+    assert!(matches!(
+        error("# old\nmobility = 3"),
+        WeightsError::UnknownWeight { line: 2, name } if name == "mobility"
+    ));
+    assert!(matches!(error("center = 2.5"), WeightsError::InvalidValue { line: 1, value } if value == "2.5"));
+
+    let missing = Weights::load("no-such-dir/weights.txt").unwrap_err();
+    assert!(matches!(missing, WeightsError::Io(error) if error.kind() == std::io::ErrorKind::NotFound));
+}
@@ -0,0 +1,111 @@
+// The built in layouts are valid and fair, and broken definitions are caught.
+
+use abalone::keyvalue::InvalidLine;
+use abalone::{Game, Layout, LayoutError, Player, Space};
+
+const WHITE: [&str; 14] = ["A1", "A2", "A3", "A4", "A5", "B1", "B2", "B3", "B4", "B5", "B6", "C3", "C4", "C5"];
+const BLACK: [&str; 14] = ["I5", "I6", "I7", "I8", "I9", "H4", "H5", "H6", "H7", "H8", "H9", "G5", "G6", "G7"];
+
+#[test]
+fn built_in_layouts_are_valid() {
+    for name in Layout::built_in_names() {
+        let layout = Layout::named(name).unwrap();
+        assert!(layout.is_symmetric(), "{} is not symmetric", name);
+
+        let game = Game::new_layout(name).unwrap();
+        game.validate_state();
+        assert_eq!((game.white_pieces, game.black_pieces), (14, 14));
+        assert_eq!(game.hash(), game.zobrist());
+        assert_eq!(game.player, Player::White);
+    }
+}
+
+#[test]
+fn belgian_daisy() {
+    let game = Game::new_belgian_daisy();
+    assert_eq!(game.to_position(), "BB1WW/BBBWWW/1BB1WW1/8/9/8/1WW1BB1/WWWBBB/WW1BB w 0 0 0");
+}
+
+#[test]
+fn names() {
+    assert_eq!(Game::new_layout("Belgian_Daisy"), Some(Game::new_belgian_daisy()));
+    assert_eq!(Game::new_layout("basic"), Some(Game::new_basic()));
+    assert_eq!(Game::new_layout("dutch"), None);
+}
+
+#[test]
+fn invalid_definitions() {
+    let mut black = BLACK;
+    black[1] = "I5";
+    assert_eq!(Layout::new("twice", &WHITE, &black), Err(LayoutError::Overlap("I5".to_string())));
+    black[1] = "A1";
+    assert_eq!(Layout::new("shared", &WHITE, &black), Err(LayoutError::Overlap("A1".to_string())));
+
+    black[1] = "I4";
+    assert_eq!(Layout::new("off the board", &WHITE, &black), Err(LayoutError::InvalidCell("I4".to_string())));
+
+    assert_eq!(
+        Layout::new("short", &WHITE[1..], &BLACK),
+        Err(LayoutError::WrongCount { player: Player::White, count: 13 })
+    );
+}
+
+#[test]
+fn symmetry() {
+    assert!(Layout::symmetric("standard", &WHITE, &BLACK).is_ok());
+
+    // moving one black marble along its row breaks the symmetry
+    let mut black = BLACK;
+    black[13] = "G8";
+    assert!(Layout::new("lopsided", &WHITE, &black).is_ok());
+    assert_eq!(Layout::symmetric("lopsided", &WHITE, &black), Err(LayoutError::Asymmetric));
+}
+
+#[test]
+fn parse() {
+    let text = "
+        # the standard layout with one black marble moved along its row
+        name = lopsided
+        white = A1 A2 A3 A4 A5, B1 B2 B3 B4 B5 B6, C3 C4 C5
+        black = I5,I6,I7,I8,I9,H4,H5,H6,H7,H8,H9,G5,G6,G8
+    ";
+    let layout: Layout = text.parse().unwrap();
+    assert_eq!(layout.name, "lopsided");
+    assert!(!layout.is_symmetric());
+    assert_eq!(layout.game().space(5, 7), Space::Occupied(Player::Black));
+
+    for name in Layout::built_in_names() {
+        let layout = Layout::named(name).unwrap();
+        assert_eq!(layout.to_string().parse::<Layout>().unwrap(), layout);
+    }
+
+    assert_eq!(
+        "white A1".parse::<Layout>(),
+        Err(LayoutError::InvalidLine(InvalidLine { line: 1, text: "white A1".to_string() }))
+    );
+    assert_eq!(
+        "\n# comment\nred = A1".parse::<Layout>(),
+        Err(LayoutError::UnknownKey { line: 3, name: "red".to_string() })
+    );
+    assert_eq!(
+        "white = A1 A2".parse::<Layout>(),
+        Err(LayoutError::WrongCount { player: Player::White, count: 2 })
+    );
+}
+
+#[test]
+fn find() {
+    assert_eq!(Layout::find("Snakes").unwrap(), Layout::named("snakes").unwrap());
+    assert_eq!(Layout::find("no-such-layout"), Err(LayoutError::UnknownLayout("no-such-layout".to_string())));
+
+    let path = std::env::temp_dir().join(format!("abalone-layout-{}.txt", std::process::id()));
+    std::fs::write(&path, Layout::named("pyramid").unwrap().to_string()).unwrap();
+    let loaded = Layout::find(path.to_str().unwrap());
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.unwrap(), Layout::named("pyramid").unwrap());
+
+    // a file that cannot be read keeps the error that said why
+    let missing = Layout::load("no-such-dir/layout.txt").unwrap_err();
+    assert_eq!(missing, LayoutError::Io(std::io::ErrorKind::NotFound.into()));
+    assert!(matches!(missing, LayoutError::Io(error) if error.kind() == std::io::ErrorKind::NotFound));
+}
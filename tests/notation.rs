@@ -0,0 +1,63 @@
+// Move notation round-trips for every legal move and rejects malformed moves.
+
+use abalone::{Game, Move, ParseMoveError};
+
+// the built in layouts, and positions a few dozen moves into each of them
+fn positions() -> Vec<Game> {
+    let mut positions = Vec::new();
+
+    for start in [Game::new_basic(), Game::new_german_daisy(), Game::new_belgian_daisy()] {
+        let mut game = start;
+        positions.push(game);
+
+        for ply in 0..40 {
+            let moves = game.get_legal_moves();
+            game = game.make_move(&moves[(ply * 7) % moves.len()]);
+            if game.game_over {
+                break;
+            }
+            if ply % 10 == 9 {
+                positions.push(game);
+            }
+        }
+    }
+
+    positions
+}
+
+#[test]
+fn legal_moves_round_trip() {
+    for game in positions() {
+        for next_move in game.get_legal_moves() {
+            assert_eq!(next_move.to_string().parse::<Move>(), Ok(next_move), "{}", next_move);
+        }
+    }
+}
+
+#[test]
+fn examples() {
+    assert_eq!("A1B2".parse::<Move>(), Ok(Move::Inline { start: (4, 0), dir: (0, 1) }));
+    assert_eq!(
+        "a1c3d3".parse::<Move>(),
+        Ok(Move::Broadside { start: (4, 0), mid: Some((4, 1)), stop: (4, 2), dir: (-1, 0) })
+    );
+}
+
+#[test]
+fn rejects_malformed_moves() {
+    assert_eq!("A1B".parse::<Move>(), Err(ParseMoveError::InvalidLength(3)));
+    assert_eq!("J1B2".parse::<Move>(), Err(ParseMoveError::InvalidCell("J1".to_string())));
+    assert_eq!("A0B2".parse::<Move>(), Err(ParseMoveError::InvalidCell("A0".to_string())));
+
+    // off the board: row A ends at A5, row I starts at I5
+    assert_eq!("A5A6".parse::<Move>(), Err(ParseMoveError::InvalidCell("A6".to_string())));
+    assert_eq!("I5I4".parse::<Move>(), Err(ParseMoveError::InvalidCell("I4".to_string())));
+
+    assert_eq!("A1A3".parse::<Move>(), Err(ParseMoveError::NotAdjacent("A1".to_string(), "A3".to_string())));
+    assert_eq!(
+        "A1A2C4".parse::<Move>(),
+        Err(ParseMoveError::NotAdjacent("A2".to_string(), "C4".to_string()))
+    );
+    assert_eq!("A1C2D3".parse::<Move>(), Err(ParseMoveError::NotInLine("A1".to_string(), "C2".to_string())));
+    assert_eq!("A1A3A4".parse::<Move>(), Err(ParseMoveError::BroadsideAlongLine));
+}
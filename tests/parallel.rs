@@ -0,0 +1,43 @@
+// Many threads searching one shared tree lose no playouts and leave no virtual losses behind.
+
+use std::thread;
+
+use abalone::parallel::shared_tree_search;
+use abalone::{Game, Policies, SearchLimits, SharedTree};
+
+#[test]
+fn every_iteration_reaches_the_root() {
+    let tree = SharedTree::new(Game::new_german_daisy()).into_arena();
+    let limits = SearchLimits::iterations(600);
+
+    let (arena, progress) = shared_tree_search(tree, &limits, &Policies::default(), 4, |_, _| ());
+
+    assert!(progress.iterations >= 600);
+    assert_eq!(arena.nodes[0].visits, progress.iterations as i32);
+
+    let children = arena.nodes[0].first_child_id.unwrap()..arena.nodes[0].last_child_id.unwrap();
+    let child_visits: i32 = children.map(|child| arena.nodes[child].visits).sum();
+    assert_eq!(child_visits, arena.nodes[0].visits);
+}
+
+#[test]
+fn no_virtual_loss_is_left() {
+    for policies in [Policies::default(), Policies::default().with_transpositions(1 << 12)] {
+        let tree = SharedTree::new(Game::new_basic());
+
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    let mut path = Vec::new();
+                    for _ in 0..150 {
+                        tree.iteration(&policies, &mut path);
+                    }
+                });
+            }
+        });
+
+        assert_eq!(tree.in_flight(), 0);
+        let arena = tree.into_arena();
+        assert_eq!(arena.nodes[0].visits, 600);
+    }
+}
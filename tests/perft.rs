@@ -0,0 +1,108 @@
+// Known perft counts for the built in layouts, and small positions whose moves can be
+// counted by hand. The depth 4 counts take a while without optimisations, so they only run
+// with `cargo test --release -- --ignored`.
+
+use abalone::{divide, perft, Game, Move, Player, Space};
+
+fn counts(game: &Game, depth: usize) -> Vec<u64> {
+    (1..=depth).map(|depth| perft(game, depth)).collect()
+}
+
+// a board holding only the given white marbles, white to move
+fn white_marbles(cells: &[(usize, usize)]) -> Game {
+    let mut game = Game::new_game();
+    for &(x, y) in cells {
+        game.set(x, y, Space::Occupied(Player::White));
+    }
+    game
+}
+
+#[test]
+fn standard() {
+    assert_eq!(counts(&Game::new_basic(), 3), [44, 1936, 98912]);
+}
+
+#[test]
+fn german_daisy() {
+    assert_eq!(counts(&Game::new_german_daisy(), 3), [80, 6244, 493480]);
+}
+
+#[test]
+fn belgian_daisy() {
+    assert_eq!(counts(&Game::new_belgian_daisy(), 3), [52, 2692, 149322]);
+}
+
+#[test]
+#[ignore]
+fn depth_four() {
+    assert_eq!(perft(&Game::new_basic(), 4), 5045110);
+    assert_eq!(perft(&Game::new_german_daisy(), 4), 38240570);
+    assert_eq!(perft(&Game::new_belgian_daisy(), 4), 8270666);
+}
+
+#[test]
+fn lone_marble() {
+    assert_eq!(perft(&white_marbles(&[(4, 4)]), 1), 6);
+    // in a corner only three neighbours are on the board
+    assert_eq!(perft(&white_marbles(&[(0, 0)]), 1), 3);
+}
+
+// Two marbles: five single marble moves each, the pair moving along its line both ways,
+// and four broadsides.
+#[test]
+fn pair() {
+    assert_eq!(perft(&white_marbles(&[(4, 4), (5, 4)]), 1), 5 + 5 + 2 + 4);
+    assert_eq!(perft(&white_marbles(&[(4, 4), (5, 5)]), 1), 5 + 5 + 2 + 4);
+    assert_eq!(perft(&white_marbles(&[(4, 4), (4, 5)]), 1), 5 + 5 + 2 + 4);
+}
+
+// Three in a line: the single marbles, the two pairs moving away from the third marble, the
+// three moving along the line both ways, and four broadsides for each pair and the triple.
+#[test]
+fn triple() {
+    assert_eq!(perft(&white_marbles(&[(3, 4), (4, 4), (5, 4)]), 1), 5 + 4 + 5 + 2 + 2 + 12);
+}
+
+#[test]
+fn pushes() {
+    let push = Move::Inline { start: (2, 4), dir: (1, 0) };
+
+    // two push one
+    let mut game = white_marbles(&[(2, 4), (3, 4)]);
+    game.set(4, 4, Space::Occupied(Player::Black));
+    assert!(game.get_legal_moves().contains(&push));
+    let after = game.make_move(&push);
+    assert_eq!(after.space(2, 4), Space::Empty);
+    assert_eq!(after.space(4, 4), Space::Occupied(Player::White));
+    assert_eq!(after.space(5, 4), Space::Occupied(Player::Black));
+
+    // but not two
+    game.set(5, 4, Space::Occupied(Player::Black));
+    assert!(!game.get_legal_moves().contains(&push));
+
+    // and one cannot push one
+    game.set(2, 4, Space::Empty);
+    assert!(!game.get_legal_moves().contains(&Move::Inline { start: (3, 4), dir: (1, 0) }));
+}
+
+#[test]
+fn push_off() {
+    let mut game = white_marbles(&[(6, 4), (7, 4)]);
+    game.set(8, 4, Space::Occupied(Player::Black));
+    game.black_pieces = 1;
+
+    let push = Move::Inline { start: (6, 4), dir: (1, 0) };
+    assert!(game.get_legal_moves().contains(&push));
+    let after = game.make_move(&push);
+    assert_eq!(after.black_pieces, 0);
+    assert_eq!(after.space(8, 4), Space::Occupied(Player::White));
+}
+
+#[test]
+fn divide_adds_up() {
+    for game in [Game::new_basic(), Game::new_german_daisy(), Game::new_belgian_daisy()] {
+        let counts = divide(&game, 3);
+        assert_eq!(counts.len() as u64, perft(&game, 1));
+        assert_eq!(counts.iter().map(|(_, count)| count).sum::<u64>(), perft(&game, 3));
+    }
+}
@@ -0,0 +1,44 @@
+// Playout policies by name, and how playouts that are cut off are scored.
+
+use std::sync::Arc;
+
+use abalone::eval::Weights;
+use abalone::playout::{DepthLimited, PlayoutKind, PlayoutPolicy, RandomPlayout};
+use abalone::{Game, Player};
+
+#[test]
+fn kinds_by_name() {
+    for (name, kind) in [
+        ("random", PlayoutKind::Random),
+        ("greedy", PlayoutKind::Greedy),
+        ("heuristic", PlayoutKind::Heuristic),
+        ("epsilon-greedy", PlayoutKind::EpsilonGreedy),
+        ("Depth-Limited", PlayoutKind::DepthLimited),
+    ] {
+        assert_eq!(name.parse::<PlayoutKind>(), Ok(kind));
+    }
+    assert!("minimax".parse::<PlayoutKind>().is_err());
+}
+
+#[test]
+fn depth_limited_scores_the_cut_off_position() {
+    // both sides have all their marbles, but white holds the centre and black the edge
+    let game: Game = "BBBBB/BBBBBB/2BBB2/8/2WWWWW2/1WWWWW2/2WWWW1/6/5 b 0 0 0".parse().unwrap();
+    let depth_limited = DepthLimited { depth: 0, policy: Arc::new(RandomPlayout), weights: Weights::default() };
+    assert_eq!(depth_limited.playout(&game, None), Some(Player::White));
+
+    // with only material counted the same position is a draw
+    let material = Weights { center: 0, cohesion: 0, edge_danger: 0, pushing: 0, ..Weights::default() };
+    let depth_limited = DepthLimited { weights: material, ..depth_limited };
+    assert_eq!(depth_limited.playout(&game, None), None);
+
+    // a shorter limit from the search still applies
+    let depth_limited = DepthLimited { depth: 50, weights: Weights::default(), ..depth_limited };
+    assert_eq!(depth_limited.playout(&game, Some(0)), Some(Player::White));
+}
+
+#[test]
+fn equal_material_cut_offs_go_by_the_evaluation() {
+    let game: Game = "BBBBB/BBBBBB/2BBB2/8/2WWWWW2/1WWWWW2/2WWWW1/6/5 b 0 0 0".parse().unwrap();
+    assert_eq!(RandomPlayout.playout(&game, Some(0)), Some(Player::White));
+}
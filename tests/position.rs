@@ -0,0 +1,84 @@
+// Position strings name what is wrong with them when they do not parse.
+
+use abalone::{Game, ParsePositionError, Player};
+
+const START: &str = "BBBBB/BBBBBB/2BBB2/8/9/8/2WWW2/WWWWWW/WWWWW w 0 0 0";
+
+fn parse(s: &str) -> Result<Game, ParsePositionError> {
+    Game::from_position(s)
+}
+
+#[test]
+fn the_start() {
+    assert_eq!(parse(START).unwrap().to_position(), Game::new_basic().to_position());
+    // lower case marbles and player are accepted too
+    assert_eq!(parse(&START.replace('B', "b")).unwrap().to_position(), START);
+    assert_eq!(parse(&START.replace(" w ", " W ")).unwrap().to_position(), START);
+}
+
+#[test]
+fn wrong_field_count() {
+    assert_eq!(parse(""), Err(ParsePositionError::WrongFieldCount(0)));
+    assert_eq!(parse(&START.replace(" 0 0 0", " 0 0")), Err(ParsePositionError::WrongFieldCount(4)));
+    assert_eq!(parse(&format!("{} 0", START)), Err(ParsePositionError::WrongFieldCount(6)));
+}
+
+#[test]
+fn wrong_row_count() {
+    assert_eq!(parse(&START.replace("/9/", "/")), Err(ParsePositionError::WrongRowCount(8)));
+    assert_eq!(parse(&START.replace("/9/", "/9/9/")), Err(ParsePositionError::WrongRowCount(10)));
+}
+
+#[test]
+fn invalid_row_length() {
+    // rows run from I at the top, which has five cells, down to A
+    let short = START.replacen("BBBBB", "BBBB", 1);
+    assert_eq!(parse(&short), Err(ParsePositionError::InvalidRowLength { row: 'I', expected: 5, found: 4 }));
+
+    let long = START.replace("/9/", "/91/");
+    assert_eq!(parse(&long), Err(ParsePositionError::InvalidRowLength { row: 'E', expected: 9, found: 10 }));
+}
+
+#[test]
+fn invalid_character() {
+    assert_eq!(parse(&START.replace("/9/", "/4x4/")), Err(ParsePositionError::InvalidCharacter('x')));
+    assert_eq!(parse(&START.replace("/9/", "/0/")), Err(ParsePositionError::InvalidCharacter('0')));
+}
+
+#[test]
+fn invalid_player() {
+    assert_eq!(parse(&START.replace(" w ", " x ")), Err(ParsePositionError::InvalidPlayer("x".to_string())));
+    assert_eq!(
+        parse(&START.replace(" w ", " white ")),
+        Err(ParsePositionError::InvalidPlayer("white".to_string()))
+    );
+}
+
+#[test]
+fn invalid_counts() {
+    for (counts, bad) in [("-1 0 0", "-1"), ("0 x 0", "x"), ("0 0 1.5", "1.5"), ("0 0 99999", "99999")] {
+        let position = START.replace("0 0 0", counts);
+        assert_eq!(parse(&position), Err(ParsePositionError::InvalidNumber(bad.to_string())), "{}", counts);
+    }
+}
+
+#[test]
+fn score_mismatch() {
+    // black has pushed off no white marbles, but one is missing from the board
+    let missing_white = START.replacen("WWWWW ", "WWWW1 ", 1);
+    assert_eq!(
+        parse(&missing_white),
+        Err(ParsePositionError::ScoreMismatch { player: Player::White, score: 0, on_board: 13 })
+    );
+
+    // white claims two black marbles that are still on the board
+    let claimed = START.replace(" w 0 0 0", " w 2 0 0");
+    assert_eq!(
+        parse(&claimed),
+        Err(ParsePositionError::ScoreMismatch { player: Player::Black, score: 2, on_board: 14 })
+    );
+
+    // the scores match once the marbles are gone
+    let pushed_off = START.replacen("WWWWW ", "WWWW1 ", 1).replace(" w 0 0 0", " w 0 1 0");
+    assert_eq!(parse(&pushed_off).unwrap().white_pieces, 13);
+}
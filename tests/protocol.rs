@@ -0,0 +1,180 @@
+// The engine protocol, driven the way a GUI would: a command at a time, waiting on replies.
+
+use std::io::{self, BufReader, PipeWriter, Write};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use abalone::protocol::run;
+use abalone::{Game, Move};
+
+// everything the engine has written so far
+#[derive(Clone, Default)]
+struct Output(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+struct Session {
+    input: Option<PipeWriter>,
+    output: Output,
+    // lines already returned by `reply`
+    read: usize,
+    engine: JoinHandle<io::Result<()>>,
+}
+
+impl Session {
+    fn start() -> Session {
+        let (reader, input) = io::pipe().unwrap();
+        let output = Output::default();
+        let engine_output = output.clone();
+        let engine = thread::spawn(move || run(BufReader::new(reader), engine_output));
+        Session { input: Some(input), output, read: 0, engine }
+    }
+
+    fn send(&mut self, line: &str) {
+        writeln!(self.input.as_mut().unwrap(), "{}", line).unwrap();
+    }
+
+    // the lines written since the last reply, up to and including one starting with `last`
+    fn reply(&mut self, last: &str) -> Vec<String> {
+        let deadline = Instant::now() + Duration::from_secs(60);
+        loop {
+            let text = String::from_utf8(self.output.0.lock().unwrap().clone()).unwrap();
+            let lines: Vec<String> = text.lines().skip(self.read).map(str::to_string).collect();
+            if let Some(end) = lines.iter().position(|line| line.starts_with(last)) {
+                self.read += end + 1;
+                return lines[..=end].to_vec();
+            }
+            assert!(Instant::now() < deadline, "no {} in {:?}", last, lines);
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    // sends `line` and returns what the engine answers before `isready` is acknowledged
+    fn ask(&mut self, line: &str) -> Vec<String> {
+        self.send(line);
+        self.send("isready");
+        let mut lines = self.reply("readyok");
+        lines.pop();
+        lines
+    }
+
+    // searches and returns the info line that ends the search and the best move
+    fn go(&mut self, args: &str) -> (String, String) {
+        self.send(&format!("go {}", args));
+        let lines = self.reply("bestmove");
+        (lines[lines.len() - 2].clone(), lines[lines.len() - 1].clone())
+    }
+
+    fn finish(mut self) {
+        self.send("quit");
+        drop(self.input.take());
+        self.engine.join().unwrap().unwrap();
+    }
+}
+
+fn field<'a>(line: &'a str, name: &str) -> &'a str {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let at = words.iter().position(|&word| word == name).unwrap();
+    words[at + 1]
+}
+
+fn position_after(moves: &[&str]) -> String {
+    let mut game = Game::new_basic();
+    for text in moves {
+        game = game.make_move(&text.parse::<Move>().unwrap());
+    }
+    format!("position {}", game.to_position())
+}
+
+#[test]
+fn position_with_moves() {
+    let mut session = Session::start();
+    let start = Game::new_basic();
+    let legal = start.get_legal_moves();
+    let first = legal[0].to_string();
+    let second = start.make_move(&legal[0]).get_legal_moves()[0].to_string();
+
+    let shown = session.ask(&format!("position startpos moves {} {}", first, second));
+    assert!(shown.is_empty(), "{:?}", shown);
+    assert_eq!(session.ask("show").last().unwrap(), &position_after(&[&first, &second]));
+
+    let fen = start.to_position();
+    session.ask(&format!("position fen {} moves {}", fen, first));
+    assert_eq!(session.ask("show").last().unwrap(), &position_after(&[&first]));
+
+    session.ask(&format!("play {}", second));
+    assert_eq!(session.ask("show").last().unwrap(), &position_after(&[&first, &second]));
+    session.finish();
+}
+
+#[test]
+fn illegal_moves_are_rejected() {
+    let mut session = Session::start();
+    let first = Game::new_basic().get_legal_moves()[0].to_string();
+    session.ask(&format!("position startpos moves {}", first));
+
+    // the same move again is not legal for the other side, and nothing is played
+    let replies = session.ask(&format!("position startpos moves {} {}", first, first));
+    assert_eq!(replies, [format!("error {} is not a legal move", first)]);
+    assert_eq!(session.ask("show").last().unwrap(), &position_after(&[&first]));
+
+    let replies = session.ask(&format!("play {}", first));
+    assert_eq!(replies, [format!("error {} is not a legal move", first)]);
+    let replies = session.ask("play nonsense");
+    assert_eq!(replies.len(), 1);
+    assert!(replies[0].starts_with("error nonsense"), "{:?}", replies);
+    assert_eq!(session.ask("show").last().unwrap(), &position_after(&[&first]));
+    session.finish();
+}
+
+#[test]
+fn go_finds_a_legal_move() {
+    let mut session = Session::start();
+    let legal = Game::new_basic().get_legal_moves();
+
+    for engine in ["mcts", "alphabeta"] {
+        session.ask(&format!("engine {}", engine));
+        let limit = if engine == "mcts" { "iterations 200" } else { "iterations 2" };
+        let (info, best) = session.go(limit);
+        assert!(info.starts_with("info"), "{}", info);
+
+        let best_move: Move = field(&best, "bestmove").parse().unwrap();
+        assert!(legal.contains(&best_move), "{}", best);
+        assert_eq!(session.ask("show").last().unwrap(), &position_after(&[]));
+    }
+
+    let replies = session.ask("go iterations");
+    assert_eq!(replies, ["error iterations needs a number"]);
+    session.finish();
+}
+
+#[test]
+fn newgame_forgets_the_game_and_the_tree() {
+    let mut session = Session::start();
+    let pv_length = |best: &str| best.split_whitespace().skip(3).count();
+
+    // one more iteration on a kept tree still reports the line the long search found
+    session.go("iterations 1000");
+    let (_, kept) = session.go("iterations 1");
+    assert!(pv_length(&kept) >= 2, "{}", kept);
+
+    let first = Game::new_basic().get_legal_moves()[0].to_string();
+    session.ask(&format!("play {}", first));
+    assert!(session.ask("newgame").is_empty());
+    assert_eq!(session.ask("show").last().unwrap(), &position_after(&[]));
+
+    // while a fresh tree has only tried one move
+    let (_, fresh) = session.go("iterations 1");
+    assert_eq!(pv_length(&fresh), 1, "{}", fresh);
+    session.finish();
+}
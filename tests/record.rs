@@ -0,0 +1,94 @@
+// Game records survive a trip through their text format, and broken records are rejected.
+
+use abalone::{Game, GameRecord, GameResult, Move, RecordError};
+
+// a record of `plies` moves from `start`, each the first legal move
+fn record_from(start: Game, plies: usize) -> GameRecord {
+    let mut record = GameRecord::new(start);
+    record.white = Some("mcts 1000".to_string());
+    record.black = Some("random".to_string());
+    record.date = Some("2022-08-01".to_string());
+
+    for _ in 0..plies {
+        let game = record.final_position();
+        record.push(game.get_legal_moves()[0]).unwrap();
+    }
+    record
+}
+
+fn assert_same(parsed: &GameRecord, record: &GameRecord) {
+    assert_eq!(parsed.white, record.white);
+    assert_eq!(parsed.black, record.black);
+    assert_eq!(parsed.date, record.date);
+    assert_eq!(parsed.time_control, record.time_control);
+    assert_eq!(parsed.start(), record.start());
+    assert_eq!(parsed.moves(), record.moves());
+    assert_eq!(parsed.result, record.result);
+}
+
+#[test]
+fn round_trip() {
+    let record = record_from(Game::new_basic(), 45);
+    let parsed: GameRecord = record.to_string().parse().unwrap();
+    assert_same(&parsed, &record);
+
+    // starting with black to move numbers the first move `n...`
+    let start = Game::new_german_daisy().make_move(&Game::new_german_daisy().get_legal_moves()[3]);
+    let mut record = record_from(start, 9);
+    record.time_control = Some("300+5".to_string());
+    record.result = GameResult::WhiteWins;
+    let text = record.to_string();
+    assert!(text.contains("1... "), "{}", text);
+    assert_same(&text.parse().unwrap(), &record);
+}
+
+#[test]
+fn push_and_pop() {
+    let mut record = record_from(Game::new_belgian_daisy(), 6);
+    let positions = record.replay();
+
+    let last = record.pop().unwrap();
+    assert_eq!(record.final_position(), positions[5]);
+    assert_eq!(record.push(last).unwrap(), positions[6]);
+
+    record.pop();
+    record.pop();
+    let other = positions[4].get_legal_moves()[1];
+    assert_eq!(record.push(other).unwrap(), positions[4].make_move(&other));
+    assert_eq!(record.moves().len(), 5);
+}
+
+#[test]
+fn invalid_records() {
+    let header = "[Position \"BBBBB/BBBBBB/2BBB2/8/9/8/2WWW2/WWWWWW/WWWWW w 0 0 0\"]\n\n";
+    let parse = |text: &str| text.parse::<GameRecord>().map(|_| ());
+
+    assert!(matches!(parse("[White mcts]\n"), Err(RecordError::InvalidTag(_))));
+    assert!(matches!(parse("[Position \"9 w 0 0 0\"]\n"), Err(RecordError::InvalidPosition(_))));
+    assert!(matches!(parse("[Result \"2-0\"]\n"), Err(RecordError::InvalidResult(_))));
+    assert!(matches!(
+        parse(&format!("{}1. A1B2 I5I4 *", header)),
+        Err(RecordError::InvalidMove { ply: 1, .. })
+    ));
+    assert!(matches!(
+        parse(&format!("{}1. A1B2 I5H5 2. A1B2 *", header)),
+        Err(RecordError::IllegalMove { ply: 2, attempted: Move::Inline { start: (4, 0), dir: (0, 1) } })
+    ));
+    // a game that is not over keeps the result it was given, as when someone resigned
+    let resigned: GameRecord = format!("[Result \"1-0\"]\n{}1. A1B2 1-0", header).parse().unwrap();
+    assert_eq!(resigned.result, GameResult::WhiteWins);
+
+    // but the tag and the end of the moves must say the same
+    assert!(matches!(
+        parse(&format!("[Result \"1-0\"]\n{}1. A1B2 *", header)),
+        Err(RecordError::ConflictingResults { tag: GameResult::WhiteWins, movetext: GameResult::Unfinished })
+    ));
+
+    // white is down to eight marbles, so the game is already over
+    let over = "[Position \"BBBBB/BBBBBB/2BBB2/8/9/8/7/WWW3/WWWWW w 0 6 0\"]\n\n";
+    assert!(matches!(parse(&format!("{}1. A1B2 0-1", over)), Err(RecordError::MoveAfterGameOver { ply: 0 })));
+    assert!(matches!(
+        parse(&format!("[Result \"1-0\"]\n{}", over)),
+        Err(RecordError::ResultMismatch { recorded: GameResult::WhiteWins, actual: GameResult::BlackWins })
+    ));
+}
@@ -0,0 +1,138 @@
+// Properties every position reached by legal play must have, checked over random games
+// from the built in layouts.
+
+use proptest::prelude::*;
+
+use abalone::{Game, Move, Player, Space};
+
+fn pieces(game: &Game, player: Player) -> i16 {
+    match player {
+        Player::White => game.white_pieces,
+        Player::Black => game.black_pieces,
+    }
+}
+
+fn offset(cell: (usize, usize), dir: (i8, i8), steps: i8) -> (i8, i8) {
+    (cell.0 as i8 + dir.0 * steps, cell.1 as i8 + dir.1 * steps)
+}
+
+fn space(game: &Game, cell: (i8, i8)) -> Space {
+    if cell.0 < 0 || cell.1 < 0 {
+        return Space::OutOfBounds;
+    }
+    game.space(cell.0 as usize, cell.1 as usize)
+}
+
+// the cells holding `player`'s marbles
+fn marbles(game: &Game, player: Player) -> Vec<(usize, usize)> {
+    (0..9)
+        .flat_map(|x| (0..9).map(move |y| (x, y)))
+        .filter(|&(x, y)| game.space(x, y) == Space::Occupied(player))
+        .collect()
+}
+
+// Plays `picks` from a layout, each pick choosing among the legal moves by its remainder,
+// and returns every position on the way that is not over.
+fn random_game(layout: usize, picks: &[usize]) -> Vec<Game> {
+    let mut game = match layout {
+        0 => Game::new_basic(),
+        1 => Game::new_german_daisy(),
+        _ => Game::new_belgian_daisy(),
+    };
+    let mut positions = Vec::new();
+
+    for pick in picks {
+        if game.game_over {
+            break;
+        }
+        positions.push(game);
+
+        let moves = game.get_legal_moves();
+        game = game.make_move(&moves[pick % moves.len()]);
+    }
+
+    positions
+}
+
+// checks one legal move against the rules it has to follow
+fn check_move(game: &Game, next_move: &Move) -> Result<(), TestCaseError> {
+    let mover = game.player;
+    let other = mover.opponent();
+    let after = game.make_move(next_move);
+
+    after.validate_state();
+    prop_assert_eq!(after.hash(), after.zobrist());
+
+    // the mover's marbles all stay on the board
+    let before_own = marbles(game, mover);
+    let after_own = marbles(&after, mover);
+    prop_assert_eq!(pieces(&after, mover), pieces(game, mover));
+    let vacated = before_own.iter().filter(|cell| !after_own.contains(cell)).count();
+
+    let lost = pieces(game, other) - pieces(&after, other);
+    prop_assert!(lost == 0 || lost == 1, "{:?} loses {} marbles", next_move, lost);
+
+    match *next_move {
+        Move::Inline { start, dir } => {
+            let own = (0..)
+                .take_while(|&steps| space(game, offset(start, dir, steps)) == Space::Occupied(mover))
+                .count() as i8;
+            let theirs = (own..)
+                .take_while(|&steps| space(game, offset(start, dir, steps)) == Space::Occupied(other))
+                .count() as i8;
+            let beyond = space(game, offset(start, dir, own + theirs));
+
+            // a sumito needs more marbles than it pushes, and room for what it pushes
+            prop_assert!((1..=3).contains(&own), "{:?} moves {} marbles", next_move, own);
+            // the line moves up one, so only the cell at its back is left empty
+            prop_assert_eq!(vacated, 1);
+            prop_assert!(theirs < own, "{:?} pushes {} with {}", next_move, theirs, own);
+            if theirs == 0 {
+                prop_assert_eq!(beyond, Space::Empty);
+            } else {
+                prop_assert!(matches!(beyond, Space::Empty | Space::OutOfBounds));
+            }
+
+            // a marble is lost exactly when the last one pushed goes off the edge
+            prop_assert_eq!(lost == 1, theirs > 0 && beyond == Space::OutOfBounds);
+        }
+        Move::Broadside { start, mid, stop, dir } => {
+            prop_assert_eq!(lost, 0);
+            prop_assert_eq!(vacated, if mid.is_some() { 3 } else { 2 });
+
+            for cell in [Some(start), mid, Some(stop)].into_iter().flatten() {
+                prop_assert_eq!(game.space(cell.0, cell.1), Space::Occupied(mover));
+                prop_assert_eq!(space(game, offset(cell, dir, 1)), Space::Empty);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(48))]
+
+    #[test]
+    fn legal_moves_follow_the_rules(layout in 0..3usize, picks in prop::collection::vec(any::<usize>(), 0..120)) {
+        for game in random_game(layout, &picks) {
+            game.validate_state();
+
+            let moves = game.get_legal_moves();
+            prop_assert!(!moves.is_empty());
+            for (i, next_move) in moves.iter().enumerate() {
+                prop_assert!(!moves[..i].contains(next_move), "{:?} is listed twice", next_move);
+                check_move(&game, next_move)?;
+            }
+        }
+    }
+
+    #[test]
+    fn positions_round_trip(layout in 0..3usize, picks in prop::collection::vec(any::<usize>(), 0..200)) {
+        for game in random_game(layout, &picks) {
+            let parsed = Game::from_position(&game.to_position()).unwrap();
+            prop_assert_eq!(parsed, game);
+            prop_assert_eq!(parsed.move_number, game.move_number);
+        }
+    }
+}
@@ -0,0 +1,107 @@
+// Every search limit ends a search, alone or with others.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use abalone::search::{mcts_search_tree, mcts_search_with_limits, SearchProgress};
+use abalone::{ArenaTree, Game, Policies, SearchLimits};
+
+fn run(limits: &SearchLimits) -> SearchProgress {
+    let mut tree = ArenaTree { nodes: Vec::new() };
+    tree.new_node(Game::new_basic(), None, None);
+    mcts_search_tree(&mut tree, limits, &Policies::default(), |_, _| ())
+}
+
+fn stop_flag(raised: bool) -> Arc<AtomicBool> {
+    Arc::new(AtomicBool::new(raised))
+}
+
+#[test]
+fn reached() {
+    let progress = SearchProgress { iterations: 10, nodes: 500, elapsed: Duration::from_millis(30) };
+
+    assert!(SearchLimits::iterations(10).reached(&progress));
+    assert!(!SearchLimits::iterations(11).reached(&progress));
+    assert!(SearchLimits::nodes(500).reached(&progress));
+    assert!(!SearchLimits::nodes(501).reached(&progress));
+    assert!(SearchLimits::time(Duration::from_millis(30)).reached(&progress));
+    assert!(!SearchLimits::time(Duration::from_millis(31)).reached(&progress));
+
+    // the first limit reached ends the search, and without any only the stop flag does
+    let limits = SearchLimits { iterations: Some(1000), nodes: Some(100), ..Default::default() };
+    assert!(limits.reached(&progress));
+    assert!(!SearchLimits::default().reached(&progress));
+    let limits = SearchLimits { stop: Some(stop_flag(false)), ..Default::default() };
+    assert!(!limits.reached(&progress) && !limits.is_stopped());
+    limits.stop.as_ref().unwrap().store(true, Ordering::Relaxed);
+    assert!(limits.reached(&progress) && limits.is_stopped());
+}
+
+#[test]
+fn iteration_and_node_limits() {
+    assert_eq!(run(&SearchLimits::iterations(37)).iterations, 37);
+
+    let progress = run(&SearchLimits::nodes(300));
+    assert!(progress.nodes >= 300);
+    // it stops at the first iteration past the limit
+    let before = run(&SearchLimits::iterations(progress.iterations - 1));
+    assert!(before.nodes < 300 || progress.iterations == 1);
+}
+
+#[test]
+fn time_limit() {
+    let limit = Duration::from_millis(150);
+    let start = Instant::now();
+    let progress = run(&SearchLimits::time(limit));
+
+    assert!(progress.elapsed >= limit);
+    assert!(start.elapsed() < limit + Duration::from_secs(2));
+}
+
+#[test]
+fn stop_flag_ends_a_search() {
+    // raised before the search starts, it still runs the one iteration every search runs
+    let limits = SearchLimits { stop: Some(stop_flag(true)), ..Default::default() };
+    assert_eq!(run(&limits).iterations, 1);
+    let tree = mcts_search_with_limits(Game::new_basic(), &limits, &Policies::default());
+    assert!(tree.best_move().is_some());
+
+    // raised by another thread, it ends a search that has no other limit
+    let stop = stop_flag(false);
+    let limits = SearchLimits { stop: Some(Arc::clone(&stop)), ..Default::default() };
+    let raiser = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        stop.store(true, Ordering::Relaxed);
+    });
+    let progress = run(&limits);
+    raiser.join().unwrap();
+    assert!(progress.elapsed >= Duration::from_millis(100));
+}
+
+#[test]
+fn stop_when_decided() {
+    // a root whose first move is so far ahead that the rest of the budget cannot change it
+    let mut tree = ArenaTree { nodes: Vec::new() };
+    tree.new_node(Game::new_basic(), None, None);
+    mcts_search_tree(&mut tree, &SearchLimits::iterations(1), &Policies::default(), |_, _| ());
+    let first = tree.nodes[0].first_child_id.unwrap();
+    tree.nodes[first].visits = 100_000;
+
+    let limits = SearchLimits { stop_when_decided: true, ..SearchLimits::iterations(50_000) };
+    let mut copy = ArenaTree { nodes: tree.nodes.clone() };
+    let progress = mcts_search_tree(&mut copy, &limits, &Policies::default(), |_, _| ());
+    assert!(progress.iterations < 1000, "{}", progress.iterations);
+
+    // only a budget that could still overtake it keeps the search going
+    let limits = SearchLimits { stop_when_decided: true, ..SearchLimits::iterations(150_000) };
+    let limits = SearchLimits { nodes: Some(600), ..limits };
+    let mut copy = ArenaTree { nodes: tree.nodes.clone() };
+    let progress = mcts_search_tree(&mut copy, &limits, &Policies::default(), |_, _| ());
+    assert!(progress.nodes >= 600);
+
+    // without the flag the whole budget is used
+    let progress = mcts_search_tree(&mut tree, &SearchLimits::iterations(300), &Policies::default(), |_, _| ());
+    assert_eq!(progress.iterations, 300);
+}
@@ -0,0 +1,42 @@
+// The selection policies choose between children as their rules say.
+
+use abalone::selection::{capture_prior, Candidate, Puct, SelectionPolicy, Ucb1};
+use abalone::{Game, Player, Space};
+
+// a position with black to move, and the same position with one black marble pushed off
+fn positions() -> (Game, Game) {
+    let quiet = Game::new_basic().make_move(&Game::new_basic().get_legal_moves()[0]);
+    let mut captured = quiet;
+    captured.set(0, 4, Space::Empty);
+    captured.black_pieces -= 1;
+    assert_eq!(quiet.player, Player::Black);
+    captured.validate_state();
+
+    (quiet, captured)
+}
+
+#[test]
+fn capture_prior_favours_captures() {
+    let (quiet, captured) = positions();
+    assert!(capture_prior(&captured) > 10.0 * capture_prior(&quiet));
+}
+
+#[test]
+fn puct_follows_the_prior() {
+    let (quiet, captured) = positions();
+    let candidates = [
+        Candidate { visits: 2.0, wins: 1.0, game_state: &quiet },
+        Candidate { visits: 2.0, wins: 1.0, game_state: &captured },
+    ];
+
+    // with nothing else to tell them apart UCB1 takes the first, PUCT the capture
+    assert_eq!(Ucb1::default().select(4.0, &candidates), 0);
+    assert_eq!(Puct::default().select(4.0, &candidates), 1);
+
+    // the prior only steers exploration, a clearly better record still wins
+    let candidates = [
+        Candidate { visits: 200.0, wins: 180.0, game_state: &quiet },
+        Candidate { visits: 200.0, wins: 40.0, game_state: &captured },
+    ];
+    assert_eq!(Puct::default().select(400.0, &candidates), 0);
+}
@@ -0,0 +1,140 @@
+// The transposition table stores, replaces and shares what it is given.
+
+use abalone::search::mcts_search_with_limits;
+use abalone::transposition::{NodeStats, TranspositionTable};
+use abalone::{ArenaTree, Game, Move, Player, Policies, SearchLimits};
+
+// a few distinct positions from the standard layout
+fn positions() -> Vec<Game> {
+    let start = Game::new_basic();
+    start.get_legal_moves().iter().take(4).map(|next_move| start.make_move(next_move)).collect()
+}
+
+#[test]
+fn store_and_probe() {
+    let games = positions();
+    let mut table = TranspositionTable::new(1000);
+    assert_eq!(table.len(), 1024);
+
+    assert_eq!(table.get(&games[0]), None);
+    assert!(table.insert(&games[0], 7, 1));
+    assert_eq!(table.get(&games[0]), Some(&7));
+
+    *table.get_mut(&games[0]).unwrap() += 1;
+    assert_eq!(table.get(&games[0]), Some(&8));
+
+    assert!(table.update(&games[1], 100, |value| {
+        *value += 1;
+        3
+    }));
+    assert_eq!(table.get(&games[1]), Some(&101));
+
+    table.clear();
+    assert_eq!(table.get(&games[0]), None);
+    assert_eq!(table.get(&games[1]), None);
+}
+
+#[test]
+fn replacement_by_priority_and_generation() {
+    let games = positions();
+    // one slot, so every position competes for it
+    let mut table = TranspositionTable::new(1);
+
+    assert!(table.insert(&games[0], 'a', 5));
+    assert!(!table.insert(&games[1], 'b', 4));
+    assert_eq!(table.get(&games[0]), Some(&'a'));
+    assert_eq!(table.get(&games[1]), None);
+
+    // the same position is always updated, and an equal priority replaces
+    assert!(table.insert(&games[0], 'c', 1));
+    assert!(table.insert(&games[1], 'd', 1));
+    assert_eq!(table.get(&games[0]), None);
+    assert_eq!(table.get(&games[1]), Some(&'d'));
+
+    // whatever an earlier search stored gives way
+    assert!(table.insert(&games[2], 'e', 100));
+    table.new_search();
+    assert!(table.insert(&games[3], 'f', 1));
+    assert_eq!(table.get(&games[3]), Some(&'f'));
+    assert!(!table.insert(&games[2], 'g', 0));
+}
+
+// Two lines from the standard layout that reach the same position, playing two white moves
+// in either order around the same black reply.
+fn transposed_lines() -> ([Move; 3], [Move; 3]) {
+    let start = Game::new_basic();
+    let moves = start.get_legal_moves();
+    let reply = start.make_move(&moves[0]).get_legal_moves()[0];
+
+    moves[1..]
+        .iter()
+        .map(|&other| ([moves[0], reply, other], [other, reply, moves[0]]))
+        .find(|(one, two)| play(one) == play(two))
+        .unwrap()
+}
+
+fn play(line: &[Move]) -> Game {
+    line.iter().fold(Game::new_basic(), |game, next_move| game.make_move(next_move))
+}
+
+#[test]
+fn transpositions_share_statistics() {
+    let (first, second) = transposed_lines();
+    let (first, second) = (play(&first), play(&second));
+    assert_eq!(first.hash(), second.hash());
+
+    let mut table = TranspositionTable::new(64);
+    NodeStats::record(&mut table, &first, Some(Player::White));
+    NodeStats::record(&mut table, &second, Some(Player::Black));
+    assert_eq!(NodeStats::lookup(Some(&table), &second, 1, 0), NodeStats { visits: 2, wins: 1 });
+
+    // a node that knows more than the table keeps its own statistics
+    assert_eq!(NodeStats::lookup(Some(&table), &second, 5, 4), NodeStats { visits: 5, wins: 4 });
+    assert_eq!(NodeStats::lookup(None, &second, 1, 0), NodeStats { visits: 1, wins: 0 });
+}
+
+#[test]
+fn tree_nodes_share_statistics() {
+    let (first, second) = transposed_lines();
+
+    // the same position reached down two lines of one tree
+    let mut tree = ArenaTree { nodes: Vec::new() };
+    tree.new_node(Game::new_basic(), None, None);
+    let leaves: Vec<usize> = [first, second]
+        .iter()
+        .map(|line| {
+            line.iter().fold(0, |parent, next_move| {
+                let game = tree.nodes[parent].game_state.make_move(next_move);
+                tree.new_node(game, Some(parent), Some(*next_move))
+            })
+        })
+        .collect();
+
+    let mut table = TranspositionTable::new(1 << 10);
+    tree.backpropagate_with_table(leaves[0], Some(Player::Black), &mut table);
+    tree.backpropagate_with_table(leaves[1], Some(Player::Black), &mut table);
+    tree.backpropagate_with_table(leaves[1], Some(Player::White), &mut table);
+
+    // each node saw its own playouts, the position all three
+    assert_eq!((tree.nodes[leaves[0]].visits, tree.nodes[leaves[1]].visits), (1, 2));
+    let position = tree.nodes[leaves[0]].game_state;
+    assert_eq!(table.get(&position), Some(&NodeStats { visits: 3, wins: 1 }));
+    assert_eq!(table.get(&Game::new_basic()).unwrap().visits, 3);
+}
+
+#[test]
+fn new_games_start_with_an_empty_table() {
+    let policies = Policies::default().with_transpositions(1 << 10);
+    mcts_search_with_limits(Game::new_basic(), &SearchLimits::iterations(50), &policies);
+    let used = policies.transpositions.as_ref().unwrap();
+    assert!(used.lock().unwrap().get(&Game::new_basic()).is_some());
+
+    let fresh = policies.with_fresh_transpositions();
+    let table = fresh.transpositions.as_ref().unwrap().lock().unwrap();
+    assert_eq!(table.len(), 1 << 10);
+    assert_eq!(table.get(&Game::new_basic()), None);
+    // the old table is left to whoever still searches with it
+    assert!(used.lock().unwrap().get(&Game::new_basic()).is_some());
+
+    assert!(Policies::default().with_fresh_transpositions().transpositions.is_none());
+}
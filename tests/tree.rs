@@ -0,0 +1,222 @@
+// Search trees keep the right statistics when they are re-rooted, merged and carried
+// between moves.
+
+use abalone::search::{mcts_search_tree, mcts_search_with_limits, Algorithm};
+use abalone::{ArenaTree, Game, Policies, SearchLimits};
+
+fn search(game: Game, iterations: usize) -> ArenaTree {
+    mcts_search_with_limits(game, &SearchLimits::iterations(iterations), &Policies::default())
+}
+
+fn children(tree: &ArenaTree, node_id: usize) -> std::ops::Range<usize> {
+    let node = &tree.nodes[node_id];
+    match (node.first_child_id, node.last_child_id) {
+        (Some(first), Some(last)) => first..last,
+        _ => 0..0,
+    }
+}
+
+fn most_visited_child(tree: &ArenaTree, node_id: usize) -> usize {
+    children(tree, node_id).max_by_key(|&child| tree.nodes[child].visits).unwrap()
+}
+
+// the number of nodes under and including node_id
+fn size(tree: &ArenaTree, node_id: usize) -> usize {
+    1 + children(tree, node_id).map(|child| size(tree, child)).sum::<usize>()
+}
+
+// every node is linked to its parent and children are contiguous after their parent
+fn assert_well_formed(tree: &ArenaTree) {
+    assert_eq!(tree.nodes[0].parent_id, None);
+    assert_eq!(tree.nodes[0].move_from_parent, None);
+
+    for node_id in 0..tree.nodes.len() {
+        for child in children(tree, node_id) {
+            assert!(child > node_id);
+            assert_eq!(tree.nodes[child].parent_id, Some(node_id));
+            let next_move = tree.nodes[child].move_from_parent.unwrap();
+            assert_eq!(tree.nodes[node_id].game_state.make_move(&next_move), tree.nodes[child].game_state);
+        }
+    }
+    assert_eq!(size(tree, 0), tree.nodes.len());
+}
+
+#[test]
+fn subtree_keeps_statistics() {
+    let tree = search(Game::new_basic(), 400);
+    let child = most_visited_child(&tree, 0);
+
+    let subtree = tree.subtree(child);
+    assert_well_formed(&subtree);
+    assert_eq!(subtree.nodes.len(), size(&tree, child));
+    assert_eq!(subtree.nodes[0].game_state, tree.nodes[child].game_state);
+    assert_eq!((subtree.nodes[0].visits, subtree.nodes[0].wins), (tree.nodes[child].visits, tree.nodes[child].wins));
+
+    let old: Vec<_> = children(&tree, child).map(|id| tree.nodes[id]).collect();
+    let new: Vec<_> = children(&subtree, 0).map(|id| subtree.nodes[id]).collect();
+    assert_eq!(old.len(), new.len());
+    for (old, new) in old.iter().zip(&new) {
+        assert_eq!((old.move_from_parent, old.visits, old.wins), (new.move_from_parent, new.visits, new.wins));
+    }
+}
+
+#[test]
+fn reroot_two_moves_down() {
+    let tree = search(Game::new_german_daisy(), 600);
+    let child = most_visited_child(&tree, 0);
+    let grandchild = most_visited_child(&tree, child);
+    let reached = tree.nodes[grandchild];
+
+    let mut rerooted = ArenaTree { nodes: tree.nodes.clone() };
+    assert!(rerooted.reroot(&reached.game_state, 2));
+    assert_well_formed(&rerooted);
+    assert_eq!(rerooted.nodes.len(), size(&tree, grandchild));
+    assert_eq!(rerooted.nodes[0].game_state, reached.game_state);
+    assert_eq!((rerooted.nodes[0].visits, rerooted.nodes[0].wins), (reached.visits, reached.wins));
+
+    // the root itself is found without changing anything
+    let mut same = ArenaTree { nodes: tree.nodes.clone() };
+    assert!(same.reroot(&tree.nodes[0].game_state, 2));
+    assert_eq!(same.nodes.len(), tree.nodes.len());
+
+    // a position deeper than asked for, or not in the tree at all, is not found
+    let mut shallow = ArenaTree { nodes: tree.nodes.clone() };
+    assert!(!shallow.reroot(&reached.game_state, 1));
+    assert_eq!(shallow.nodes.len(), tree.nodes.len());
+    assert!(!shallow.reroot(&Game::new_basic(), 2));
+}
+
+#[test]
+fn search_with_tree_carries_statistics() {
+    let algorithm = Algorithm::default();
+    let limits = SearchLimits::iterations(300);
+    let game = Game::new_basic();
+
+    let mut tree = None;
+    let outcome = algorithm.search_with_tree(game, &limits, 1, &mut tree);
+    let first = tree.as_ref().unwrap();
+
+    // the opponent answers with the reply the search expected most
+    let child = children(first, 0).find(|&id| first.nodes[id].move_from_parent == Some(outcome.best_move)).unwrap();
+    let reply = most_visited_child(first, child);
+    let carried = first.nodes[reply].visits;
+    let next = first.nodes[reply].game_state;
+    assert!(carried > 0);
+
+    algorithm.search_with_tree(next, &limits, 1, &mut tree);
+    let second = tree.as_ref().unwrap();
+    assert_eq!(second.nodes[0].game_state, next);
+    assert!(second.nodes[0].visits >= carried + 300);
+}
+
+#[test]
+fn node_limits_count_only_new_nodes() {
+    let limits = SearchLimits::nodes(600);
+
+    // a tree kept from an earlier search is already past the limit
+    let mut tree = search(Game::new_basic(), 150);
+    let kept = tree.nodes.len();
+    assert!(kept > 600);
+    let first = mcts_search_tree(&mut tree, &limits, &Policies::default(), |_, _| ());
+    assert!(first.nodes >= 600 && tree.nodes.len() == kept + first.nodes);
+
+    let second = mcts_search_tree(&mut tree, &limits, &Policies::default(), |_, _| ());
+    assert!(second.iterations > 1 && second.nodes >= 600);
+    assert_eq!(tree.nodes.len(), kept + first.nodes + second.nodes);
+
+    // the same through the tree kept between moves, searched by several threads
+    let algorithm = Algorithm::default();
+    let mut tree = None;
+    algorithm.search_with_tree(Game::new_basic(), &limits, 2, &mut tree);
+    let after_first = tree.as_ref().unwrap().nodes.len();
+    assert!(after_first >= 601);
+    algorithm.search_with_tree(Game::new_basic(), &limits, 2, &mut tree);
+    assert!(tree.as_ref().unwrap().nodes.len() >= after_first + 600);
+}
+
+// A tree of the root and all its children, listed in reverse move generation order if
+// `reverse`, with `stats(index)` as the visits and wins of the child for the index-th move
+// (plus 100 one level down). The child for the first move is expanded the same way.
+fn tree_in_order(root: Game, reverse: bool, stats: impl Fn(usize) -> (i32, i32)) -> ArenaTree {
+    let mut tree = ArenaTree { nodes: Vec::new() };
+    tree.new_node(root, None, None);
+
+    let expand = |tree: &mut ArenaTree, node_id: usize, depth: usize| {
+        let game = tree.nodes[node_id].game_state;
+        let mut moves: Vec<_> = game.get_legal_moves().into_iter().enumerate().collect();
+        if reverse {
+            moves.reverse();
+        }
+
+        tree.nodes[node_id].first_child_id = Some(tree.nodes.len());
+        for (index, next_move) in moves {
+            let child = tree.new_node(game.make_move(&next_move), Some(node_id), Some(next_move));
+            let (visits, wins) = stats(index + 100 * depth);
+            tree.nodes[child].visits = visits;
+            tree.nodes[child].wins = wins;
+        }
+        tree.nodes[node_id].last_child_id = Some(tree.nodes.len());
+    };
+
+    expand(&mut tree, 0, 0);
+    let first_move = root.get_legal_moves()[0];
+    let first = children(&tree, 0).find(|&id| tree.nodes[id].move_from_parent == Some(first_move)).unwrap();
+    expand(&mut tree, first, 1);
+
+    tree
+}
+
+// the visits and wins of the node reached by `moves` from the root
+fn stats_after(tree: &ArenaTree, moves: &[abalone::Move]) -> (i32, i32) {
+    let mut node_id = 0;
+    for next_move in moves {
+        node_id = children(tree, node_id).find(|&id| tree.nodes[id].move_from_parent == Some(*next_move)).unwrap();
+    }
+    (tree.nodes[node_id].visits, tree.nodes[node_id].wins)
+}
+
+#[test]
+fn merge_matches_children_by_move() {
+    let root = Game::new_basic();
+    let forward = tree_in_order(root, false, |index| (index as i32 + 1, index as i32 / 2));
+    let backward = tree_in_order(root, true, |index| (1000 + index as i32, 10 * index as i32));
+
+    let merged = forward.merge_trees(&backward);
+    assert_well_formed(&merged);
+    assert_eq!(merged.nodes.len(), forward.nodes.len());
+
+    let moves = root.get_legal_moves();
+    for next_move in &moves {
+        let (a, b) = (stats_after(&forward, &[*next_move]), stats_after(&backward, &[*next_move]));
+        assert_eq!(stats_after(&merged, &[*next_move]), (a.0 + b.0, a.1 + b.1), "{}", next_move);
+    }
+
+    let first = moves[0];
+    for reply in root.make_move(&first).get_legal_moves() {
+        let line = [first, reply];
+        let (a, b) = (stats_after(&forward, &line), stats_after(&backward, &line));
+        assert_eq!(stats_after(&merged, &line), (a.0 + b.0, a.1 + b.1), "{} {}", first, reply);
+    }
+}
+
+#[test]
+fn merge_copies_what_only_one_side_expanded() {
+    let root = Game::new_basic();
+    let mut shallow = tree_in_order(root, false, |_| (1, 0));
+    // forget the second level, so only the other tree has it
+    let first = children(&shallow, 0).find(|&id| !children(&shallow, id).is_empty()).unwrap();
+    shallow.nodes.truncate(children(&shallow, 0).end);
+    shallow.nodes[first].first_child_id = None;
+    shallow.nodes[first].last_child_id = None;
+
+    let deep = tree_in_order(root, true, |index| (index as i32 + 1, 1));
+    let merged = shallow.merge_trees(&deep);
+    assert_well_formed(&merged);
+    assert_eq!(merged.nodes.len(), deep.nodes.len());
+
+    let first_move = root.get_legal_moves()[0];
+    for reply in root.make_move(&first_move).get_legal_moves() {
+        let line = [first_move, reply];
+        assert_eq!(stats_after(&merged, &line), stats_after(&deep, &line));
+    }
+}